@@ -1,4 +1,5 @@
 use clap::Parser;
+use drink::session::accounts::{KeyPair, KeyScheme};
 use sp_core::crypto::{AccountId32, Ss58Codec};
 
 #[derive(Parser)]
@@ -21,7 +22,7 @@ pub enum CliCommand {
         value: u128,
     },
     SetActor {
-        #[clap(value_parser = AccountId32::from_ss58check)]
+        #[clap(value_parser = parse_account)]
         actor: AccountId32,
     },
     SetGasLimit {
@@ -43,6 +44,19 @@ pub enum CliCommand {
         message: String,
         args: Vec<String>,
     },
+
+    SaveTranscript {
+        path: String,
+    },
+}
+
+/// Parses an SS58 address or a name of a development account (like `Alice`).
+fn parse_account(account: &str) -> Result<AccountId32, String> {
+    AccountId32::from_ss58check(account).or_else(|_| {
+        KeyPair::from_name(account, KeyScheme::Sr25519)
+            .map(|keypair| keypair.account_id())
+            .map_err(|err| format!("Invalid account {account}: {err:?}"))
+    })
 }
 
 #[cfg(test)]
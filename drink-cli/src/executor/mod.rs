@@ -61,6 +61,8 @@ pub fn execute(app_state: &mut AppState) -> Result<()> {
             salt,
         } => contract::deploy(app_state, constructor, args, salt),
         CliCommand::Call { message, args } => contract::call(app_state, message, args),
+
+        CliCommand::SaveTranscript { path } => save_transcript(app_state, path),
     }
 
     Ok(())
@@ -69,7 +71,6 @@ pub fn execute(app_state: &mut AppState) -> Result<()> {
 fn build_blocks(app_state: &mut AppState, count: u32) {
     app_state.chain_info.block_height = app_state
         .session
-        .sandbox()
         .build_blocks(count)
         .expect("Failed to build block - chain is broken");
 
@@ -79,9 +80,16 @@ fn build_blocks(app_state: &mut AppState, count: u32) {
 fn add_tokens(app_state: &mut AppState, recipient: AccountId32, value: u128) -> Result<()> {
     app_state
         .session
-        .sandbox()
         .mint_into(recipient.clone(), value)
-        .map_err(|err| anyhow::format_err!("Failed to add token: {err:?}"))?;
+        .map_err(|err| anyhow::format_err!("Failed to add token: {err}"))?;
     app_state.print(&format!("{value} tokens added to {recipient}",));
     Ok(())
 }
+
+fn save_transcript(app_state: &mut AppState, path: String) {
+    let target = app_state.ui_state.cwd.join(path);
+    match app_state.session.record().transcript().save(&target) {
+        Ok(_) => app_state.print(&format!("Transcript saved to {}", target.display())),
+        Err(err) => app_state.print_error(&err.to_string()),
+    }
+}
@@ -29,12 +29,16 @@ pub(super) fn build(_app_state: &AppState) -> impl Widget {
         ),
         command(
             "set-actor <account>",
-            "set <account> as the current actor (transaction sender)",
+            "set <account> (SS58 address or dev account name, like Alice) as the current actor",
         ),
         command(
             "set-gas-limit <ref_time> <proof_size>",
             "set gas limits to <ref_time> and <proof_size>",
         ),
+        command(
+            "save-transcript <path>",
+            "save the transcript of the session to <path>, so it can be replayed in tests",
+        ),
     ])
     .block(section("Help"))
 }
@@ -5,7 +5,14 @@ use std::{path::PathBuf, rc::Rc};
 use contract_metadata::ContractMetadata;
 use contract_transcode::ContractMessageTranscoder;
 
-use crate::{DrinkResult, Error};
+pub use self::{
+    host_functions::{HostCompatReport, HostFunction},
+    storage_compat::StorageIncompatibility,
+};
+use crate::{DrinkResult, Error, SandboxConfig};
+
+mod host_functions;
+mod storage_compat;
 
 /// A struct representing the result of parsing a `.contract` bundle file.
 ///
@@ -53,6 +60,44 @@ impl ContractBundle {
         Ok(Self { wasm, transcoder })
     }
 
+    /// Checks whether the contract state of the `old` version remains valid after upgrading to this
+    /// version, by comparing their ink! storage layouts.
+    ///
+    /// Fields and `Mapping`s are matched by their paths. All the reordered, added, removed and
+    /// retyped fields of existing storage cells, removed `Mapping`s and changed root keys are
+    /// reported. Adding new cells (like a new `Mapping` or `Lazy` field) is compatible.
+    pub fn check_storage_compat(
+        &self,
+        old: &ContractBundle,
+    ) -> Result<(), Vec<StorageIncompatibility>> {
+        let incompatibilities = storage_compat::compare(
+            &storage_compat::storage_cells(old.transcoder.metadata()),
+            &storage_compat::storage_cells(self.transcoder.metadata()),
+        );
+        match incompatibilities.is_empty() {
+            true => Ok(()),
+            false => Err(incompatibilities),
+        }
+    }
+
+    /// Lists the host functions imported by the contract code.
+    pub fn host_functions(&self) -> DrinkResult<Vec<HostFunction>> {
+        host_functions::imported_functions(&self.wasm).map_err(Error::InvalidWasm)
+    }
+
+    /// Checks which of the host functions imported by the contract code are supported by the
+    /// runtime of `Config`.
+    ///
+    /// Contracts using `#[unstable]` host functions are accepted only by runtimes with
+    /// `UnsafeUnstableInterface` enabled (see `unsafe_unstable_interface` of
+    /// `create_minimal_runtime!`).
+    pub fn host_compat<Config: SandboxConfig>(&self) -> DrinkResult<HostCompatReport>
+    where
+        Config::Runtime: pallet_contracts::Config,
+    {
+        host_functions::check::<Config>(&self.wasm)
+    }
+
     /// Load the `.contract` bundle (`contract_file_name`) located in the `project_dir`` working directory.
     ///
     /// This is meant to be used predominantly by the `local_contract_file!` macro.
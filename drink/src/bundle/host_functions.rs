@@ -0,0 +1,197 @@
+//! Compatibility of the host functions imported by a contract with a runtime.
+
+use pallet_contracts::Determinism;
+use wasmparser::{Parser, Payload, Type, TypeRef, ValType};
+
+use crate::{DrinkResult, Error, Sandbox, SandboxConfig};
+
+/// A host function imported by the contract code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HostFunction {
+    /// The module the function is imported from, like `seal0`.
+    pub module: String,
+    /// The name of the function, like `seal_input`.
+    pub name: String,
+    /// The signature in the WAT format, like `(param i32 i32) (result i32)`. `None` if it uses
+    /// types other than `i32` and `i64`, which no host function does.
+    pub signature: Option<String>,
+}
+
+/// Report on which of the host functions imported by a contract are supported by a runtime.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HostCompatReport {
+    functions: Vec<(HostFunction, bool)>,
+}
+
+impl HostCompatReport {
+    /// All the imported host functions (in the import order), together with the information
+    /// whether the runtime supports them.
+    pub fn functions(&self) -> &[(HostFunction, bool)] {
+        &self.functions
+    }
+
+    /// The imported host functions that the runtime doesn't support.
+    pub fn unsupported(&self) -> Vec<&HostFunction> {
+        self.functions
+            .iter()
+            .filter_map(|(function, supported)| (!supported).then_some(function))
+            .collect()
+    }
+
+    /// Whether the runtime supports all the imported host functions, i.e. whether it accepts the
+    /// contract code.
+    pub fn is_compatible(&self) -> bool {
+        self.functions.iter().all(|(_, supported)| *supported)
+    }
+}
+
+/// Lists the functions imported by the WASM module `wasm`.
+pub(super) fn imported_functions(wasm: &[u8]) -> Result<Vec<HostFunction>, String> {
+    let mut types = vec![];
+    let mut functions = vec![];
+
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload.map_err(|err| err.to_string())? {
+            Payload::TypeSection(reader) => {
+                for ty in reader {
+                    let Type::Func(func) = ty.map_err(|err| err.to_string())?;
+                    types.push(signature(func.params(), func.results()));
+                }
+            }
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import.map_err(|err| err.to_string())?;
+                    let TypeRef::Func(type_index) = import.ty else {
+                        continue;
+                    };
+                    let signature = types
+                        .get(type_index as usize)
+                        .ok_or_else(|| format!("Unknown type of `{}`", import.name))?
+                        .clone();
+                    functions.push(HostFunction {
+                        module: import.module.to_string(),
+                        name: import.name.to_string(),
+                        signature,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(functions)
+}
+
+fn signature(params: &[ValType], results: &[ValType]) -> Option<String> {
+    let render = |kind: &str, types: &[ValType]| {
+        let types = types
+            .iter()
+            .map(|ty| match ty {
+                ValType::I32 => Some(" i32"),
+                ValType::I64 => Some(" i64"),
+                _ => None,
+            })
+            .collect::<Option<String>>()?;
+        Some(match types.is_empty() {
+            true => String::new(),
+            false => format!("({kind}{types})"),
+        })
+    };
+    let (params, results) = (render("param", params)?, render("result", results)?);
+    Some(format!("{params} {results}").trim().to_string())
+}
+
+/// Checks which of the host functions imported by `wasm` are supported by the runtime of `Config`.
+///
+/// For every function, a module importing only this function (with the same signature) is
+/// uploaded to a fresh sandbox. The upload succeeds only if the runtime provides the function, so
+/// unstable functions are supported only with `UnsafeUnstableInterface` enabled, and deprecated
+/// ones (not available to new code) are reported as unsupported.
+pub(super) fn check<Config: SandboxConfig>(wasm: &[u8]) -> DrinkResult<HostCompatReport>
+where
+    Config::Runtime: pallet_contracts::Config,
+{
+    let functions = imported_functions(wasm).map_err(Error::InvalidWasm)?;
+    let mut sandbox = Sandbox::<Config>::new()?;
+
+    let functions = functions
+        .into_iter()
+        .map(|function| {
+            let supported = probe_module(&function).is_some_and(|code| {
+                sandbox
+                    .upload_contract(code, Config::default_actor(), None, Determinism::Enforced)
+                    .is_ok()
+            });
+            (function, supported)
+        })
+        .collect();
+
+    Ok(HostCompatReport { functions })
+}
+
+/// A minimal contract module importing only `function`.
+fn probe_module(function: &HostFunction) -> Option<Vec<u8>> {
+    let signature = function.signature.as_ref()?;
+    wat::parse_str(format!(
+        r#"(module
+            (import "env" "memory" (memory 1 1))
+            (import "{}" "{}" (func {signature}))
+            (func (export "deploy"))
+            (func (export "call"))
+        )"#,
+        function.module, function.name
+    ))
+    .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MinimalRuntime;
+
+    const CONTRACT: &str = r#"
+        (module
+            (import "seal0" "seal_input" (func (param i32 i32)))
+            (import "seal0" "no_such_function" (func (param i64) (result i32)))
+            (import "env" "memory" (memory 1 1))
+            (func (export "deploy"))
+            (func (export "call"))
+        )"#;
+
+    #[test]
+    fn imported_functions_are_listed() {
+        let functions = imported_functions(&wat::parse_str(CONTRACT).unwrap()).unwrap();
+
+        assert_eq!(
+            functions,
+            vec![
+                HostFunction {
+                    module: "seal0".to_string(),
+                    name: "seal_input".to_string(),
+                    signature: Some("(param i32 i32)".to_string()),
+                },
+                HostFunction {
+                    module: "seal0".to_string(),
+                    name: "no_such_function".to_string(),
+                    signature: Some("(param i64) (result i32)".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_functions_are_reported() {
+        let report = check::<MinimalRuntime>(&wat::parse_str(CONTRACT).unwrap()).unwrap();
+
+        assert!(!report.is_compatible());
+        assert_eq!(report.functions().len(), 2);
+        assert_eq!(
+            report
+                .unsupported()
+                .into_iter()
+                .map(|function| function.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["no_such_function"]
+        );
+    }
+}
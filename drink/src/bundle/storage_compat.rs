@@ -0,0 +1,593 @@
+//! Comparison of the ink! storage layouts of two versions of a contract.
+
+use std::{collections::BTreeMap, fmt};
+
+use ink_metadata::{layout::Layout, InkProject};
+use scale_info::{form::PortableForm, PortableRegistry, TypeDef};
+
+/// A difference between two storage layouts that would corrupt the contract state after an
+/// upgrade.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StorageIncompatibility {
+    /// A field moved to a different position within its storage cell.
+    FieldReordered {
+        /// Path of the field, like `balances.total`.
+        path: String,
+        /// Position of the field in the old version.
+        old_position: usize,
+        /// Position of the field in the new version.
+        new_position: usize,
+    },
+    /// A field (or a `Mapping` key) has a different type.
+    TypeChanged {
+        /// Path of the field.
+        path: String,
+        /// Type of the field in the old version.
+        old_type: String,
+        /// Type of the field in the new version.
+        new_type: String,
+    },
+    /// A field was removed from an existing storage cell.
+    FieldRemoved {
+        /// Path of the field.
+        path: String,
+    },
+    /// A field was added to an existing storage cell.
+    FieldAdded {
+        /// Path of the field.
+        path: String,
+    },
+    /// A field or a `Mapping` is stored under a different root key.
+    RootKeyChanged {
+        /// Path of the field.
+        path: String,
+        /// Root key in the old version.
+        old_key: u32,
+        /// Root key in the new version.
+        new_key: u32,
+    },
+    /// A `Mapping` was removed, so its entries are left orphaned.
+    MappingRemoved {
+        /// Path of the mapping.
+        path: String,
+        /// Root key of the mapping.
+        root_key: u32,
+    },
+    /// An enum variant got a different discriminant.
+    VariantDiscriminantChanged {
+        /// Path of the variant, like `state.Active`.
+        path: String,
+        /// Discriminant in the old version.
+        old_discriminant: usize,
+        /// Discriminant in the new version.
+        new_discriminant: usize,
+    },
+    /// An enum variant was removed.
+    VariantRemoved {
+        /// Path of the variant.
+        path: String,
+        /// Discriminant of the variant in the old version.
+        discriminant: usize,
+    },
+}
+
+impl fmt::Display for StorageIncompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FieldReordered {
+                path,
+                old_position,
+                new_position,
+            } => write!(
+                f,
+                "Field `{path}` moved from position {old_position} to {new_position}"
+            ),
+            Self::TypeChanged {
+                path,
+                old_type,
+                new_type,
+            } => write!(
+                f,
+                "Field `{path}` changed type from `{old_type}` to `{new_type}`"
+            ),
+            Self::FieldRemoved { path } => write!(f, "Field `{path}` was removed"),
+            Self::FieldAdded { path } => {
+                write!(f, "Field `{path}` was added to an existing cell")
+            }
+            Self::RootKeyChanged {
+                path,
+                old_key,
+                new_key,
+            } => write!(
+                f,
+                "Field `{path}` moved from root key {old_key:#010x} to {new_key:#010x}"
+            ),
+            Self::MappingRemoved { path, root_key } => write!(
+                f,
+                "Mapping `{path}` under root key {root_key:#010x} was removed"
+            ),
+            Self::VariantDiscriminantChanged {
+                path,
+                old_discriminant,
+                new_discriminant,
+            } => write!(
+                f,
+                "Variant `{path}` changed discriminant from {old_discriminant} to {new_discriminant}"
+            ),
+            Self::VariantRemoved { path, discriminant } => write!(
+                f,
+                "Variant `{path}` (discriminant {discriminant}) was removed"
+            ),
+        }
+    }
+}
+
+/// A storage cell (value stored under a single root key), flattened into its fields.
+#[derive(Clone, Debug, Default)]
+pub(super) struct Cell {
+    /// Path of the field that owns the cell.
+    path: String,
+    /// Type of the owning field, if the cell is a `Mapping`.
+    mapping: Option<String>,
+    /// The fields stored in the cell, in the encoding order.
+    fields: Vec<Field>,
+    /// Paths and discriminants of the enum variants stored in the cell.
+    variants: Vec<(String, usize)>,
+}
+
+/// A field of a storage cell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Field {
+    /// Path of the field, like `balances.total`.
+    path: String,
+    /// Type of the field.
+    ty: String,
+    /// Path of the enum variant containing the field (empty outside of enums). Positions of the
+    /// fields are compared only within the same variant, as every variant is encoded on its own.
+    /// The enum itself is represented by a field in the enclosing scope.
+    scope: String,
+}
+
+impl Cell {
+    /// Returns the position of the field at `index` among the fields of the same scope.
+    fn position(&self, index: usize) -> usize {
+        let scope = &self.fields[index].scope;
+        self.fields[..index]
+            .iter()
+            .filter(|field| &field.scope == scope)
+            .count()
+    }
+}
+
+/// Flattens the storage layout of `metadata` into cells, keyed by the root key.
+pub(super) fn storage_cells(metadata: &InkProject) -> BTreeMap<u32, Cell> {
+    let mut cells = BTreeMap::new();
+    flatten(
+        metadata.registry(),
+        metadata.layout(),
+        String::new(),
+        "",
+        0,
+        &mut cells,
+    );
+    cells
+}
+
+fn flatten(
+    registry: &PortableRegistry,
+    layout: &Layout<PortableForm>,
+    path: String,
+    scope: &str,
+    root_key: u32,
+    cells: &mut BTreeMap<u32, Cell>,
+) {
+    match layout {
+        Layout::Root(root) => {
+            let key = *root.root_key().key();
+            let is_mapping = registry
+                .resolve(root.ty().id)
+                .and_then(|ty| ty.path.segments.last())
+                .is_some_and(|name| name == "Mapping");
+            cells.insert(
+                key,
+                Cell {
+                    path: path.clone(),
+                    mapping: is_mapping.then(|| type_name(registry, root.ty().id)),
+                    ..Default::default()
+                },
+            );
+            // A new cell starts a new scope.
+            flatten(registry, root.layout(), path, "", key, cells);
+        }
+        Layout::Struct(layout) => {
+            for field in layout.fields() {
+                let path = join(&path, field.name());
+                flatten(registry, field.layout(), path, scope, root_key, cells);
+            }
+        }
+        Layout::Enum(layout) => {
+            let cell = cells.entry(root_key).or_default();
+            cell.fields.push(Field {
+                path: path.clone(),
+                ty: format!("enum {}", layout.name()),
+                scope: scope.to_string(),
+            });
+            for (discriminant, variant) in layout.variants() {
+                let path = join(&path, variant.name());
+                cells
+                    .entry(root_key)
+                    .or_default()
+                    .variants
+                    .push((path.clone(), discriminant.value()));
+                for field in variant.fields() {
+                    let field_path = join(&path, field.name());
+                    flatten(registry, field.layout(), field_path, &path, root_key, cells);
+                }
+            }
+        }
+        Layout::Array(layout) => {
+            for index in 0..layout.len() {
+                let path = format!("{path}[{index}]");
+                flatten(registry, layout.layout(), path, scope, root_key, cells);
+            }
+        }
+        Layout::Hash(layout) => flatten(registry, layout.layout(), path, scope, root_key, cells),
+        Layout::Leaf(leaf) => cells.entry(root_key).or_default().fields.push(Field {
+            path,
+            ty: type_name(registry, leaf.ty().id),
+            scope: scope.to_string(),
+        }),
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    match path {
+        "" => name.to_string(),
+        _ => format!("{path}.{name}"),
+    }
+}
+
+/// Renders the type `id` independently of its id in the registry, which differs between
+/// contract versions.
+fn type_name(registry: &PortableRegistry, id: u32) -> String {
+    let Some(ty) = registry.resolve(id) else {
+        return format!("#{id}");
+    };
+    let name_of = |id| type_name(registry, id);
+
+    if !ty.path.segments.is_empty() {
+        let path = ty.path.segments.join("::");
+        let params: Vec<_> = ty
+            .type_params
+            .iter()
+            .filter_map(|param| param.ty.map(|ty| name_of(ty.id)))
+            .collect();
+        return match params.is_empty() {
+            true => path,
+            false => format!("{path}<{}>", params.join(", ")),
+        };
+    }
+
+    match &ty.type_def {
+        TypeDef::Primitive(primitive) => format!("{primitive:?}").to_lowercase(),
+        TypeDef::Sequence(sequence) => format!("Vec<{}>", name_of(sequence.type_param.id)),
+        TypeDef::Array(array) => format!("[{}; {}]", name_of(array.type_param.id), array.len),
+        TypeDef::Tuple(tuple) => {
+            let fields: Vec<_> = tuple.fields.iter().map(|field| name_of(field.id)).collect();
+            format!("({})", fields.join(", "))
+        }
+        TypeDef::Compact(compact) => format!("Compact<{}>", name_of(compact.type_param.id)),
+        other => format!("{other:?}"),
+    }
+}
+
+/// Lists all the incompatibilities between the `old` and `new` storage cells.
+pub(super) fn compare(
+    old: &BTreeMap<u32, Cell>,
+    new: &BTreeMap<u32, Cell>,
+) -> Vec<StorageIncompatibility> {
+    let mut incompatibilities = vec![];
+
+    for (&old_key, old_cell) in old {
+        let Some(new_cell) = new.get(&old_key) else {
+            match new.iter().find(|(_, cell)| cell.path == old_cell.path) {
+                Some((&new_key, _)) => {
+                    incompatibilities.push(StorageIncompatibility::RootKeyChanged {
+                        path: old_cell.path.clone(),
+                        old_key,
+                        new_key,
+                    })
+                }
+                None if old_cell.mapping.is_some() => {
+                    incompatibilities.push(StorageIncompatibility::MappingRemoved {
+                        path: old_cell.path.clone(),
+                        root_key: old_key,
+                    })
+                }
+                None => incompatibilities.extend(old_cell.fields.iter().map(|field| {
+                    StorageIncompatibility::FieldRemoved {
+                        path: field.path.clone(),
+                    }
+                })),
+            }
+            continue;
+        };
+
+        if let (Some(old_type), Some(new_type)) = (&old_cell.mapping, &new_cell.mapping) {
+            if old_type != new_type {
+                incompatibilities.push(StorageIncompatibility::TypeChanged {
+                    path: old_cell.path.clone(),
+                    old_type: old_type.clone(),
+                    new_type: new_type.clone(),
+                });
+                continue;
+            }
+        }
+
+        for (old_index, old_field) in old_cell.fields.iter().enumerate() {
+            let path = &old_field.path;
+            let Some(new_index) = new_cell.fields.iter().position(|f| &f.path == path) else {
+                incompatibilities.push(StorageIncompatibility::FieldRemoved { path: path.clone() });
+                continue;
+            };
+            let (old_position, new_position) =
+                (old_cell.position(old_index), new_cell.position(new_index));
+            if new_position != old_position {
+                incompatibilities.push(StorageIncompatibility::FieldReordered {
+                    path: path.clone(),
+                    old_position,
+                    new_position,
+                });
+            }
+            let new_type = &new_cell.fields[new_index].ty;
+            if new_type != &old_field.ty {
+                incompatibilities.push(StorageIncompatibility::TypeChanged {
+                    path: path.clone(),
+                    old_type: old_field.ty.clone(),
+                    new_type: new_type.clone(),
+                });
+            }
+        }
+
+        for (path, old_discriminant) in &old_cell.variants {
+            match new_cell.variants.iter().find(|(p, _)| p == path) {
+                None => incompatibilities.push(StorageIncompatibility::VariantRemoved {
+                    path: path.clone(),
+                    discriminant: *old_discriminant,
+                }),
+                Some((_, new_discriminant)) if new_discriminant != old_discriminant => {
+                    incompatibilities.push(StorageIncompatibility::VariantDiscriminantChanged {
+                        path: path.clone(),
+                        old_discriminant: *old_discriminant,
+                        new_discriminant: *new_discriminant,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+
+        // Fields of new variants don't affect the existing data.
+        let is_new_scope = |scope: &String| {
+            !scope.is_empty() && !old_cell.variants.iter().any(|(path, _)| path == scope)
+        };
+        for field in &new_cell.fields {
+            if !old_cell.fields.iter().any(|f| f.path == field.path) && !is_new_scope(&field.scope)
+            {
+                incompatibilities.push(StorageIncompatibility::FieldAdded {
+                    path: field.path.clone(),
+                });
+            }
+        }
+    }
+
+    incompatibilities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(path: &str, fields: &[(&str, &str)]) -> Cell {
+        scoped_cell(
+            path,
+            &fields
+                .iter()
+                .map(|(path, ty)| (*path, *ty, ""))
+                .collect::<Vec<_>>(),
+            &[],
+        )
+    }
+
+    fn scoped_cell(path: &str, fields: &[(&str, &str, &str)], variants: &[(&str, usize)]) -> Cell {
+        Cell {
+            path: path.to_string(),
+            mapping: None,
+            fields: fields
+                .iter()
+                .map(|(path, ty, scope)| Field {
+                    path: path.to_string(),
+                    ty: ty.to_string(),
+                    scope: scope.to_string(),
+                })
+                .collect(),
+            variants: variants
+                .iter()
+                .map(|(path, discriminant)| (path.to_string(), *discriminant))
+                .collect(),
+        }
+    }
+
+    fn mapping(path: &str, ty: &str) -> Cell {
+        Cell {
+            mapping: Some(ty.to_string()),
+            ..cell(path, &[(path, "u128")])
+        }
+    }
+
+    #[test]
+    fn identical_layouts_are_compatible() {
+        let cells = BTreeMap::from([
+            (0, cell("", &[("owner", "AccountId"), ("total", "u128")])),
+            (1, mapping("balances", "Mapping<AccountId, u128>")),
+        ]);
+        assert!(compare(&cells, &cells.clone()).is_empty());
+    }
+
+    #[test]
+    fn new_cells_are_compatible() {
+        let old = BTreeMap::from([(0, cell("", &[("owner", "AccountId")]))]);
+        let mut new = old.clone();
+        new.insert(1, mapping("allowances", "Mapping<AccountId, u128>"));
+
+        assert!(compare(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn field_changes_are_reported() {
+        let old = BTreeMap::from([(
+            0,
+            cell(
+                "",
+                &[
+                    ("owner", "AccountId"),
+                    ("total", "u128"),
+                    ("paused", "bool"),
+                ],
+            ),
+        )]);
+        let new = BTreeMap::from([(
+            0,
+            cell(
+                "",
+                &[("total", "u64"), ("owner", "AccountId"), ("fee", "u8")],
+            ),
+        )]);
+
+        assert_eq!(
+            compare(&old, &new),
+            vec![
+                StorageIncompatibility::FieldReordered {
+                    path: "owner".to_string(),
+                    old_position: 0,
+                    new_position: 1
+                },
+                StorageIncompatibility::FieldReordered {
+                    path: "total".to_string(),
+                    old_position: 1,
+                    new_position: 0
+                },
+                StorageIncompatibility::TypeChanged {
+                    path: "total".to_string(),
+                    old_type: "u128".to_string(),
+                    new_type: "u64".to_string()
+                },
+                StorageIncompatibility::FieldRemoved {
+                    path: "paused".to_string()
+                },
+                StorageIncompatibility::FieldAdded {
+                    path: "fee".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn mapping_changes_are_reported() {
+        let old = BTreeMap::from([
+            (1, mapping("balances", "Mapping<AccountId, u128>")),
+            (2, mapping("allowances", "Mapping<AccountId, u128>")),
+            (3, mapping("nonces", "Mapping<AccountId, u64>")),
+        ]);
+        let new = BTreeMap::from([
+            (4, mapping("balances", "Mapping<AccountId, u128>")),
+            (3, mapping("nonces", "Mapping<u32, u64>")),
+        ]);
+
+        assert_eq!(
+            compare(&old, &new),
+            vec![
+                StorageIncompatibility::RootKeyChanged {
+                    path: "balances".to_string(),
+                    old_key: 1,
+                    new_key: 4
+                },
+                StorageIncompatibility::MappingRemoved {
+                    path: "allowances".to_string(),
+                    root_key: 2
+                },
+                StorageIncompatibility::TypeChanged {
+                    path: "nonces".to_string(),
+                    old_type: "Mapping<AccountId, u64>".to_string(),
+                    new_type: "Mapping<u32, u64>".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn enum_variants_are_compared_separately() {
+        let old = BTreeMap::from([(
+            0,
+            scoped_cell(
+                "",
+                &[
+                    ("state", "enum State", ""),
+                    ("state.Active.since", "u32", "state.Active"),
+                    ("state.Closed.at", "u32", "state.Closed"),
+                    ("state.Closed.by", "AccountId", "state.Closed"),
+                    ("owner", "AccountId", ""),
+                ],
+                &[
+                    ("state.Active", 0),
+                    ("state.Closed", 1),
+                    ("state.Paused", 2),
+                ],
+            ),
+        )]);
+        let new = BTreeMap::from([(
+            0,
+            scoped_cell(
+                "",
+                &[
+                    ("state", "enum State", ""),
+                    ("state.Active.since", "u64", "state.Active"),
+                    ("state.Active.until", "u64", "state.Active"),
+                    ("state.Closed.at", "u32", "state.Closed"),
+                    ("state.Closed.by", "AccountId", "state.Closed"),
+                    ("state.Frozen.reason", "u8", "state.Frozen"),
+                    ("owner", "AccountId", ""),
+                ],
+                &[
+                    ("state.Active", 0),
+                    ("state.Closed", 2),
+                    ("state.Frozen", 3),
+                ],
+            ),
+        )]);
+
+        // Changes in one variant don't shift the fields of the other variants nor the fields after
+        // the enum, and a new variant is compatible.
+        assert_eq!(
+            compare(&old, &new),
+            vec![
+                StorageIncompatibility::TypeChanged {
+                    path: "state.Active.since".to_string(),
+                    old_type: "u32".to_string(),
+                    new_type: "u64".to_string()
+                },
+                StorageIncompatibility::VariantDiscriminantChanged {
+                    path: "state.Closed".to_string(),
+                    old_discriminant: 1,
+                    new_discriminant: 2
+                },
+                StorageIncompatibility::VariantRemoved {
+                    path: "state.Paused".to_string(),
+                    discriminant: 2
+                },
+                StorageIncompatibility::FieldAdded {
+                    path: "state.Active.until".to_string()
+                },
+            ]
+        );
+    }
+}
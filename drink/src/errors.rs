@@ -14,9 +14,20 @@ pub enum Error {
     /// Block couldn't have been finalized.
     #[error("Failed to finalize block: {0}")]
     BlockFinalize(String),
+    /// The chain cannot be moved to the requested height.
+    #[error("Cannot warp to block {target}, the current block is {current}")]
+    InvalidTargetHeight {
+        /// The requested height.
+        target: u64,
+        /// The height of the current block.
+        current: u64,
+    },
     /// Bundle loading and parsing has failed
     #[error("Loading the contract bundle has failed: {0}")]
     BundleLoadFailed(String),
+    /// Contract code couldn't have been parsed.
+    #[error("Parsing the contract code has failed: {0}")]
+    InvalidWasm(String),
 }
 
 /// Every contract message wraps its return value in `Result<T, LangResult>`. This is the error
@@ -14,7 +14,7 @@ pub mod session;
 
 use std::sync::{Arc, Mutex};
 
-pub use bundle::ContractBundle;
+pub use bundle::{ContractBundle, HostCompatReport, HostFunction, StorageIncompatibility};
 pub use drink_test_macro::{contract_bundle_provider, test};
 pub use errors::Error;
 pub use frame_support::{
@@ -39,4 +39,9 @@ impl<AccountId: Ord> MockRegistry<AccountId> {
     pub fn get(&self, address: &AccountId) -> Option<&ContractMock> {
         self.mocked_contracts.get(address)
     }
+
+    /// Removes the mock for `address`. Returns the removed mock, if any.
+    pub fn remove(&mut self, address: &AccountId) -> Option<ContractMock> {
+        self.mocked_contracts.remove(address)
+    }
 }
@@ -1,8 +1,11 @@
 //! Module containing the [`Runtime`](Runtime) trait and its example implementations. You can use
 //! `drink` with any runtime that implements the `Runtime` trait.
 
+pub mod call_filter;
+pub mod chain_extension;
 pub mod minimal;
 pub mod pallet_contracts_debugging;
+pub mod presets;
 pub use frame_metadata::RuntimeMetadataPrefixed;
 pub use minimal::MinimalRuntime;
 
@@ -11,3 +14,6 @@ pub type AccountIdFor<R> = <R as frame_system::Config>::AccountId;
 
 /// The type of a hash.
 pub type HashFor<R> = <R as frame_system::Config>::Hash;
+
+/// The type of a runtime event.
+pub type RuntimeEventOf<R> = <R as frame_system::Config>::RuntimeEvent;
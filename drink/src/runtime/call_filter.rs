@@ -0,0 +1,127 @@
+//! Filtering and observing the runtime calls dispatched by contracts (with `call_runtime`).
+//!
+//! [`SandboxCallFilter`] can be used as the `CallFilter` of `pallet_contracts`. Every runtime call
+//! that a contract tries to dispatch is passed, through a runtime interface, to the `CallFilterExt`
+//! runtime extension, which decides whether the call is allowed. Without the extension, no call
+//! is allowed, just like with `CallFilter = ()`.
+//!
+//! Notice that the filter is applied to the origin of the dispatched call, so it is consulted
+//! also for the calls nested in it (like the calls of a `pallet_utility` batch).
+//!
+//! The filter only decides whether a call is dispatched. It cannot replace the call with a mocked
+//! result: allowed calls are executed by the actual pallets of the runtime.
+//!
+//! `call_runtime` is an unstable host function, so contracts using it can be uploaded only to
+//! runtimes with the unstable interface enabled (`unsafe_unstable_interface: true` in
+//! `create_minimal_runtime!`). With the default `MinimalRuntime`, such contracts are rejected
+//! before the filter is ever consulted.
+
+use std::sync::{Arc, Mutex};
+
+use frame_support::traits::Contains;
+use parity_scale_codec::{Decode, Encode};
+use sp_externalities::{decl_extension, ExternalitiesExt};
+use sp_runtime_interface::runtime_interface;
+
+use crate::runtime::pallet_contracts_debugging::NoopExt;
+
+/// Contracts pallet outsources the runtime call filtering through this runtime interface.
+#[runtime_interface]
+pub trait ContractRuntimeCalls {
+    /// Asks the `CallFilterExt` runtime extension whether the encoded `call` is allowed.
+    fn filter(&mut self, call: Vec<u8>) -> bool {
+        self.extension::<CallFilterExt>()
+            .map(|ext| ext.filter_call(call))
+            .unwrap_or(false)
+    }
+}
+
+/// This trait describes a runtime extension that decides which runtime calls contracts can
+/// dispatch.
+pub trait CallFilterExtT {
+    /// Called when a contract dispatches a runtime call. `call` is the encoded `RuntimeCall`.
+    ///
+    /// Returns whether the call is allowed. A forbidden call fails with `CallRuntimeFailed`.
+    fn filter_call(&self, _call: Vec<u8>) -> bool {
+        // By default, no call is allowed.
+        false
+    }
+}
+
+decl_extension! {
+    /// A wrapper type for the `CallFilterExtT` extension.
+    pub struct CallFilterExt(Box<dyn CallFilterExtT + Send>);
+}
+
+impl CallFilterExtT for NoopExt {}
+
+/// Call filter forwarding the decision to the `CallFilterExt` runtime extension.
+pub enum SandboxCallFilter {}
+
+impl<Call: Encode> Contains<Call> for SandboxCallFilter {
+    fn contains(call: &Call) -> bool {
+        contract_runtime_calls::filter(call.encode())
+    }
+}
+
+/// An untyped runtime call filter, operating on the encoded calls.
+pub(crate) type EncodedCallFilter = Box<dyn Fn(&[u8]) -> bool + Send + Sync>;
+
+/// Wraps a typed runtime call filter into an `EncodedCallFilter`. Calls that cannot be decoded are
+/// not allowed.
+pub(crate) fn encoded_call_filter<Call: Decode + 'static>(
+    filter: impl Fn(&Call) -> bool + Send + Sync + 'static,
+) -> EncodedCallFilter {
+    Box::new(move |encoded| Call::decode(&mut &*encoded).is_ok_and(|call| filter(&call)))
+}
+
+/// The filter set on the `Session`, together with the log of the calls it has been consulted on.
+#[derive(Default)]
+pub(crate) struct CallFilterState {
+    /// The filter in use. If not set, no call is allowed.
+    pub filter: Option<EncodedCallFilter>,
+    /// Encoded calls passed to the filter (since the last drain) and whether they were allowed.
+    pub dispatched: Vec<(Vec<u8>, bool)>,
+}
+
+/// Runtime extension filtering the calls with the filter set on the `Session` and logging them.
+pub(crate) struct RecordingCallFilter {
+    /// Filter state, shared with the session.
+    pub state: Arc<Mutex<CallFilterState>>,
+}
+
+impl CallFilterExtT for RecordingCallFilter {
+    fn filter_call(&self, call: Vec<u8>) -> bool {
+        let mut state = self
+            .state
+            .lock()
+            .expect("Should be able to acquire call filter state");
+        let allowed = state.filter.as_ref().is_some_and(|filter| filter(&call));
+        state.dispatched.push((call, allowed));
+        allowed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calls_are_filtered_and_logged() {
+        let state = Arc::new(Mutex::new(CallFilterState::default()));
+        let extension = RecordingCallFilter {
+            state: Arc::clone(&state),
+        };
+
+        assert!(!extension.filter_call(vec![0]));
+
+        state.lock().unwrap().filter = Some(Box::new(|call| call[0] == 1));
+        assert!(extension.filter_call(vec![1]));
+        assert!(!extension.filter_call(vec![2]));
+
+        assert_eq!(
+            state.lock().unwrap().dispatched,
+            vec![(vec![0], false), (vec![1], true), (vec![2], false)]
+        );
+    }
+}
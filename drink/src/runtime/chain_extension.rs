@@ -0,0 +1,279 @@
+//! A chain extension whose functions are handled by closures registered at runtime.
+//!
+//! Instead of implementing `ChainExtension::call` by hand, you can use [`ChainExtensionRegistry`]
+//! as the chain extension type of your runtime (for example with `create_minimal_runtime!`) and
+//! register a handler for every `(extension_id, func_id)` pair the contract uses. Like the
+//! debugging hooks (see `pallet_contracts_debugging`), the calls leave the runtime through a
+//! runtime interface and reach the handlers via a runtime extension (`ChainExtensionExt`), so the
+//! handlers can be swapped between calls without recompiling the runtime.
+//!
+//! `Session` registers an extension forwarding the calls to the handlers registered on it, so every
+//! test can provide its own behavior for a single runtime type. With a bare `Sandbox`, you can
+//! register your own `ChainExtensionExtT` implementation.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use frame_support::{sp_runtime::DispatchError, weights::Weight};
+use pallet_contracts::chain_extension::{
+    ChainExtension, Environment, Ext, InitState, Result as ExtensionResult, RetVal,
+};
+use parity_scale_codec::{Decode, Encode};
+use sp_externalities::{decl_extension, ExternalitiesExt};
+use sp_runtime_interface::runtime_interface;
+
+use crate::runtime::pallet_contracts_debugging::NoopExt;
+
+/// Weight charged by `ChainExtensionRegistry` for every chain extension call, before the handler
+/// is called.
+pub const CHAIN_EXTENSION_BASE_WEIGHT: Weight = Weight::from_parts(100_000_000, 0);
+
+/// Result of a chain extension function: the status code returned to the contract and the data
+/// written to its output buffer.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Encode, Decode)]
+pub struct ChainExtensionOutput {
+    /// Status code (`0` usually means success).
+    pub status: u32,
+    /// Encoded output of the function.
+    pub data: Vec<u8>,
+    /// Weight charged for the function, on top of `CHAIN_EXTENSION_BASE_WEIGHT`.
+    pub weight: Weight,
+}
+
+/// Reasons for a chain extension call to fail. The contract call is then trapped.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum ChainExtensionError {
+    /// There is no handler registered for the called function.
+    MissingHandler,
+    /// The input passed by the contract couldn't be decoded as the handler input.
+    InputDecoding,
+}
+
+impl From<ChainExtensionError> for DispatchError {
+    fn from(error: ChainExtensionError) -> Self {
+        match error {
+            ChainExtensionError::MissingHandler => {
+                DispatchError::Other("No handler registered for the chain extension function")
+            }
+            ChainExtensionError::InputDecoding => {
+                DispatchError::Other("Failed to decode the chain extension input")
+            }
+        }
+    }
+}
+
+/// An untyped chain extension function handler.
+///
+/// Similarly to `MessageMock`, we cannot keep differently typed closures in a single collection,
+/// so the handlers operate on encoded input and output.
+pub type ChainExtensionHandler =
+    Box<dyn Fn(Vec<u8>) -> Result<ChainExtensionOutput, ChainExtensionError> + Send + Sync>;
+
+/// A helper function to create a chain extension handler out of a typed closure.
+///
+/// The input is decoded from the contract's input buffer, the output is encoded into its output
+/// buffer and the status code is `0`. Only `CHAIN_EXTENSION_BASE_WEIGHT` is charged for the call.
+pub fn chain_extension_handler<
+    Input: Decode,
+    Output: Encode,
+    Body: Fn(Input) -> Output + Send + Sync + 'static,
+>(
+    body: Body,
+) -> ChainExtensionHandler {
+    chain_extension_handler_with_status(move |input| (0, body(input)))
+}
+
+/// Like [`chain_extension_handler`], but the closure also returns the status code.
+pub fn chain_extension_handler_with_status<
+    Input: Decode,
+    Output: Encode,
+    Body: Fn(Input) -> (u32, Output) + Send + Sync + 'static,
+>(
+    body: Body,
+) -> ChainExtensionHandler {
+    Box::new(move |encoded_input| {
+        let input =
+            Input::decode(&mut &*encoded_input).map_err(|_| ChainExtensionError::InputDecoding)?;
+        let (status, output) = body(input);
+        Ok(ChainExtensionOutput {
+            status,
+            data: output.encode(),
+            weight: Weight::zero(),
+        })
+    })
+}
+
+/// A registry of chain extension function handlers, keyed by `(extension_id, func_id)`.
+#[derive(Default)]
+pub(crate) struct ChainExtensionHandlers {
+    handlers: BTreeMap<(u16, u16), ChainExtensionHandler>,
+}
+
+impl ChainExtensionHandlers {
+    /// Registers `handler` for the function. Returns the previous handler, if any.
+    pub fn register(
+        &mut self,
+        extension_id: u16,
+        func_id: u16,
+        handler: ChainExtensionHandler,
+    ) -> Option<ChainExtensionHandler> {
+        self.handlers.insert((extension_id, func_id), handler)
+    }
+
+    /// Removes the handler of the function. Returns the removed handler, if any.
+    pub fn remove(&mut self, extension_id: u16, func_id: u16) -> Option<ChainExtensionHandler> {
+        self.handlers.remove(&(extension_id, func_id))
+    }
+
+    /// Calls the handler of the function with `input`.
+    pub fn call(
+        &self,
+        extension_id: u16,
+        func_id: u16,
+        input: Vec<u8>,
+    ) -> Result<ChainExtensionOutput, ChainExtensionError> {
+        match self.handlers.get(&(extension_id, func_id)) {
+            None => Err(ChainExtensionError::MissingHandler),
+            Some(handler) => handler(input),
+        }
+    }
+}
+
+/// This trait describes a runtime extension that handles the calls of `ChainExtensionRegistry`.
+pub trait ChainExtensionExtT {
+    /// Called when a contract calls the chain extension function `(extension_id, func_id)`.
+    ///
+    /// The returned value must be a valid codec encoding for
+    /// `Result<ChainExtensionOutput, ChainExtensionError>`.
+    fn call(&self, _extension_id: u16, _func_id: u16, _input: Vec<u8>) -> Vec<u8> {
+        // By default, no function is supported.
+        Err::<ChainExtensionOutput, _>(ChainExtensionError::MissingHandler).encode()
+    }
+}
+
+decl_extension! {
+    /// A wrapper type for the `ChainExtensionExtT` extension.
+    pub struct ChainExtensionExt(Box<dyn ChainExtensionExtT + Send>);
+}
+
+impl ChainExtensionExtT for NoopExt {}
+
+/// Runtime extension forwarding the calls to the handlers registered on the `Session`.
+pub(crate) struct HandlersExtension {
+    /// Handler registry, shared with the session.
+    pub handlers: Arc<Mutex<ChainExtensionHandlers>>,
+}
+
+impl ChainExtensionExtT for HandlersExtension {
+    fn call(&self, extension_id: u16, func_id: u16, input: Vec<u8>) -> Vec<u8> {
+        self.handlers
+            .lock()
+            .expect("Should be able to acquire chain extension handlers")
+            .call(extension_id, func_id, input)
+            .encode()
+    }
+}
+
+/// `ChainExtensionRegistry` outsources the calls through this runtime interface.
+///
+/// The result is an encoded `Result<ChainExtensionOutput, ChainExtensionError>`.
+#[runtime_interface]
+pub trait ChainExtensionDispatcher {
+    /// Forwards the call to the `ChainExtensionExt` runtime extension.
+    fn call(&mut self, extension_id: u16, func_id: u16, input: Vec<u8>) -> Vec<u8> {
+        match self.extension::<ChainExtensionExt>() {
+            // Without the extension (e.g. in a bare `Sandbox`), there are no handlers at all.
+            None => Err::<ChainExtensionOutput, _>(ChainExtensionError::MissingHandler).encode(),
+            Some(ext) => ext.call(extension_id, func_id, input),
+        }
+    }
+}
+
+/// Chain extension forwarding every call to the handler registered for the called
+/// `(extension_id, func_id)`.
+///
+/// Every call is charged `CHAIN_EXTENSION_BASE_WEIGHT` plus the weight returned by the handler.
+/// Calling a function without a registered handler traps the contract, as well as a malformed
+/// result returned by a custom `ChainExtensionExtT` implementation.
+#[derive(Default)]
+pub struct ChainExtensionRegistry;
+
+impl<R: pallet_contracts::Config> ChainExtension<R> for ChainExtensionRegistry {
+    fn call<E: Ext<T = R>>(&mut self, env: Environment<E, InitState>) -> ExtensionResult<RetVal> {
+        let (extension_id, func_id) = (env.ext_id(), env.func_id());
+        let mut env = env.buf_in_buf_out();
+        env.charge_weight(CHAIN_EXTENSION_BASE_WEIGHT)?;
+        let input = env.read(env.in_len())?;
+
+        let result = chain_extension_dispatcher::call(extension_id, func_id, input);
+        let ChainExtensionOutput {
+            status,
+            data,
+            weight,
+        } = Result::<ChainExtensionOutput, ChainExtensionError>::decode(&mut &result[..])
+            .map_err(|_| DispatchError::Other("chain extension output decoding failed"))??;
+
+        env.charge_weight(weight)?;
+        env.write(&data, false, None)?;
+        Ok(RetVal::Converging(status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handlers_are_called_per_function() {
+        let mut handlers = ChainExtensionHandlers::default();
+        handlers.register(0, 41, chain_extension_handler(|()| 100u32));
+        handlers.register(
+            0,
+            42,
+            chain_extension_handler_with_status(|(a, b): (u32, u32)| (1, a + b)),
+        );
+
+        assert_eq!(
+            handlers.call(0, 41, vec![]),
+            Ok(ChainExtensionOutput {
+                status: 0,
+                data: 100u32.encode(),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            handlers.call(0, 42, (2u32, 3u32).encode()),
+            Ok(ChainExtensionOutput {
+                status: 1,
+                data: 5u32.encode(),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            handlers.call(1, 41, vec![]),
+            Err(ChainExtensionError::MissingHandler)
+        );
+        assert_eq!(
+            handlers.call(0, 42, vec![1]),
+            Err(ChainExtensionError::InputDecoding)
+        );
+    }
+
+    #[test]
+    fn handlers_can_be_swapped() {
+        let mut handlers = ChainExtensionHandlers::default();
+        handlers.register(0, 41, chain_extension_handler(|()| 1u32));
+        assert!(handlers
+            .register(0, 41, chain_extension_handler(|()| 2u32))
+            .is_some());
+        assert_eq!(handlers.call(0, 41, vec![]).unwrap().data, 2u32.encode());
+
+        assert!(handlers.remove(0, 41).is_some());
+        assert_eq!(
+            handlers.call(0, 41, vec![]),
+            Err(ChainExtensionError::MissingHandler)
+        );
+    }
+}
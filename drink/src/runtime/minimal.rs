@@ -17,6 +17,14 @@ macro_rules! impl_sandbox_config {
     };
     (
         $name:ident, $runtime:tt, $default_balance:expr, $default_actor:expr
+    ) => {
+        impl_sandbox_config!(
+            $name, $runtime, $default_balance, $default_actor, extra_pallets: (), genesis: {}
+        );
+    };
+    (
+        $name:ident, $runtime:tt, $default_balance:expr, $default_actor:expr,
+        extra_pallets: $extra_pallets:ty, genesis: { $( $genesis:expr ),* }
     ) => {
         impl $crate::SandboxConfig for $name {
             type Runtime = $runtime;
@@ -26,27 +34,28 @@ macro_rules! impl_sandbox_config {
                 $crate::pallet_balances::GenesisConfig::<$runtime> {
                     balances: vec![(Self::default_actor(), $default_balance)],
                 }
-                .assimilate_storage(storage)
+                .assimilate_storage(storage)?;
+                $( $genesis.assimilate_storage(storage)?; )*
+                Ok(())
             }
 
             fn initialize_block(
                 height: $crate::frame_system::pallet_prelude::BlockNumberFor<$runtime>,
                 parent_hash: <$runtime as $crate::frame_system::Config>::Hash,
             ) -> Result<(), String> {
-                use std::time::SystemTime;
                 use $crate::frame_support::traits::Hooks;
 
                 $crate::frame_system::Pallet::<$runtime>::reset_events();
                 $crate::frame_system::Pallet::<$runtime>::initialize(&height, &parent_hash, &Default::default());
                 $crate::pallet_balances::Pallet::<$runtime>::on_initialize(height);
                 $crate::pallet_timestamp::Pallet::<$runtime>::set_timestamp(
-                    SystemTime::now()
-                        .duration_since(SystemTime::UNIX_EPOCH)
-                        .expect("Time went backwards")
-                        .as_secs(),
+                    $crate::timestamp_api::next_block_timestamp(
+                        $crate::pallet_timestamp::Pallet::<$runtime>::get(),
+                    ),
                 );
                 $crate::pallet_timestamp::Pallet::<$runtime>::on_initialize(height);
                 $crate::pallet_contracts::Pallet::<$runtime>::on_initialize(height);
+                <$extra_pallets as $crate::frame_support::traits::OnInitialize<_>>::on_initialize(height);
                 $crate::frame_system::Pallet::<$runtime>::note_finished_initialize();
                 Ok(())
             }
@@ -56,6 +65,8 @@ macro_rules! impl_sandbox_config {
             ) -> Result<<$runtime as $crate::frame_system::Config>::Hash, String> {
                 use $crate::frame_support::traits::Hooks;
 
+                $crate::frame_system::Pallet::<$runtime>::note_finished_extrinsics();
+                <$extra_pallets as $crate::frame_support::traits::OnFinalize<_>>::on_finalize(height);
                 $crate::pallet_contracts::Pallet::<$runtime>::on_finalize(height);
                 $crate::pallet_timestamp::Pallet::<$runtime>::on_finalize(height);
                 $crate::pallet_balances::Pallet::<$runtime>::on_finalize(height);
@@ -80,17 +91,73 @@ macro_rules! impl_sandbox_config {
 }
 
 /// Macro creating a minimal runtime with the given name. Optionally can take a chain extension
-/// type as a second argument.
+/// type as a second argument. Use `drink::runtime::chain_extension::ChainExtensionRegistry` to
+/// handle the chain extension calls with closures registered on the `Session`.
+///
+/// Besides System, Balances, Timestamp and Contracts, the runtime can include additional pallets.
+/// Their configs are placed in the auxiliary module defining the runtime, so they can refer to
+/// `RuntimeEvent`, `RuntimeCall`, `OriginCaller` etc. directly (items from your module must be
+/// referred to with `super::`). Their genesis configs are built into the initial storage and their
+/// hooks run after (`on_initialize`) or before (`on_finalize`) the hooks of the default pallets.
+/// Each of the `pallets`, `configs` and `genesis` sections can be omitted:
+/// ```rust, ignore
+/// create_minimal_runtime!(
+///     RuntimeWithUtility,
+///     (),
+///     pallets: { Utility: pallet_utility },
+///     configs: {
+///         impl pallet_utility::Config for RuntimeWithUtility {
+///             type RuntimeEvent = RuntimeEvent;
+///             type RuntimeCall = RuntimeCall;
+///             type PalletsOrigin = OriginCaller;
+///             type WeightInfo = ();
+///         }
+///     },
+/// );
+/// ```
+///
+/// The depth of the contract call stack (5 by default) can be set with `call_stack_depth: <depth>,`
+/// right after the chain extension. Contracts using `#[unstable]` host functions are rejected,
+/// unless the unstable interface is enabled with `unsafe_unstable_interface: true,` (placed after
+/// `call_stack_depth`). This includes `call_runtime`, so the runtime call filter (see
+/// `drink::runtime::call_filter`) has no effect without it. `ContractBundle::host_compat` reports
+/// which host functions used by a contract the runtime supports. These can be followed by
+/// `parameters: { existential_deposit, deposit_per_byte, deposit_per_item, default_deposit_limit,
+/// initial_balance }` (all `u128`) overriding the balance-related defaults. The deposit prices and
+/// other `pallet_contracts` parameters, like the schedule, can also be changed in a running sandbox
+/// with the `contracts_config_api`. See `drink::runtime::presets` for examples.
 ///
 /// The new macro will automatically implement `drink::SandboxConfig`.
 #[macro_export]
 macro_rules! create_minimal_runtime {
+    (@or [] $default:expr) => { $default };
+    (@or [$value:expr] $default:expr) => { $value };
     ($name:ident) => {
         create_minimal_runtime!($name, ());
     };
     ($name:ident, $chain_extension: ty) => {
+        create_minimal_runtime!($name, $chain_extension,);
+    };
+    (
+        $name:ident,
+        $chain_extension: ty,
+        $( call_stack_depth: $call_stack_depth:expr, )?
+        $( unsafe_unstable_interface: $unsafe_unstable_interface:expr, )?
+        $( parameters: {
+            existential_deposit: $existential_deposit:expr,
+            deposit_per_byte: $deposit_per_byte:expr,
+            deposit_per_item: $deposit_per_item:expr,
+            default_deposit_limit: $default_deposit_limit:expr,
+            initial_balance: $initial_balance:expr $(,)?
+        }, )?
+        $( pallets: { $( $pallet_name:ident : $( $pallet_path:ident )::+ ),* $(,)? } $(,)? )?
+        $( configs: { $( $config:item )* } $(,)? )?
+        $( genesis: { $( $genesis:expr ),* $(,)? } $(,)? )?
+    ) => {
 
 // ------------ Put all the boilerplate into an auxiliary module -----------------------------------
+// `construct_runtime` doesn't allow doc comments for the runtime type and the types it generates.
+#[allow(missing_docs)]
 mod construct_runtime {
 
     // ------------ Bring some common types into the scope -----------------------------------------
@@ -106,7 +173,31 @@ mod construct_runtime {
         traits::{ConstBool, ConstU128, ConstU32, ConstU64, Currency, Randomness},
         weights::Weight,
     };
-    use $crate::runtime::pallet_contracts_debugging::DrinkDebug;
+    use $crate::{
+        contracts_config_api::{parameter, ContractsParameter},
+        runtime::pallet_contracts_debugging::DrinkDebug,
+    };
+
+    // ------------ Runtime parameters -------------------------------------------------------------
+
+    /// Default initial balance for the default account.
+    pub const INITIAL_BALANCE: u128 =
+        $crate::create_minimal_runtime!(@or [$( $initial_balance )?] 1_000_000_000_000_000);
+    /// Existential deposit of pallet balances.
+    pub const EXISTENTIAL_DEPOSIT: u128 =
+        $crate::create_minimal_runtime!(@or [$( $existential_deposit )?] 1);
+    /// Default storage deposit for every byte of contract storage.
+    const DEPOSIT_PER_BYTE: u128 = $crate::create_minimal_runtime!(@or [$( $deposit_per_byte )?] 1);
+    /// Default storage deposit for every item of contract storage.
+    const DEPOSIT_PER_ITEM: u128 = $crate::create_minimal_runtime!(@or [$( $deposit_per_item )?] 1);
+    /// Default storage deposit limit.
+    const DEFAULT_DEPOSIT_LIMIT: u128 =
+        $crate::create_minimal_runtime!(@or [$( $default_deposit_limit )?] 10_000_000);
+    /// Maximum depth of the contract call stack.
+    const CALL_STACK_DEPTH: usize = $crate::create_minimal_runtime!(@or [$( $call_stack_depth )?] 5);
+    /// Whether contracts can use the unstable host functions.
+    const UNSAFE_UNSTABLE_INTERFACE: bool =
+        $crate::create_minimal_runtime!(@or [$( $unsafe_unstable_interface )?] false);
 
     // ------------ Define the runtime type as a collection of pallets -----------------------------
     construct_runtime!(
@@ -115,6 +206,7 @@ mod construct_runtime {
             Balances: $crate::pallet_balances,
             Timestamp: $crate::pallet_timestamp,
             Contracts: $crate::pallet_contracts,
+            $( $( $pallet_name: $( $pallet_path )::+, )* )?
         }
     );
 
@@ -133,7 +225,7 @@ mod construct_runtime {
         type WeightInfo = ();
         type Balance = u128;
         type DustRemoval = ();
-        type ExistentialDeposit = ConstU128<1>;
+        type ExistentialDeposit = ConstU128<EXISTENTIAL_DEPOSIT>;
         type AccountStore = System;
         type ReserveIdentifier = [u8; 8];
         type FreezeIdentifier = ();
@@ -156,8 +248,9 @@ mod construct_runtime {
     // ------------ Configure pallet contracts -----------------------------------------------------
     pub enum SandboxRandomness {}
     impl Randomness<H256, u32> for SandboxRandomness {
-        fn random(_subject: &[u8]) -> (H256, u32) {
-            unreachable!("No randomness")
+        fn random(subject: &[u8]) -> (H256, u32) {
+            let block_number = System::block_number();
+            (H256($crate::randomness_api::random(subject, block_number)), block_number)
         }
     }
 
@@ -168,12 +261,16 @@ mod construct_runtime {
         }
     }
 
+    // The parameters below can be overridden in a running sandbox (see `contracts_config_api`).
     parameter_types! {
-        pub SandboxSchedule: $crate::pallet_contracts::Schedule<$name> = {
-            <$crate::pallet_contracts::Schedule<$name>>::default()
-        };
+        pub SandboxSchedule: $crate::pallet_contracts::Schedule<$name> =
+            parameter(ContractsParameter::Schedule, Default::default);
         pub DeletionWeightLimit: Weight = Weight::zero();
-        pub DefaultDepositLimit: BalanceOf = 10_000_000;
+        pub DefaultDepositLimit: BalanceOf =
+            parameter(ContractsParameter::DefaultDepositLimit, || DEFAULT_DEPOSIT_LIMIT);
+        pub DepositPerByte: BalanceOf = parameter(ContractsParameter::DepositPerByte, || DEPOSIT_PER_BYTE);
+        pub DepositPerItem: BalanceOf = parameter(ContractsParameter::DepositPerItem, || DEPOSIT_PER_ITEM);
+        pub MaxCodeLen: u32 = parameter(ContractsParameter::MaxCodeLen, || 123 * 1024);
         pub CodeHashLockupDepositPercent: Perbill = Perbill::from_percent(0);
         pub MaxDelegateDependencies: u32 = 32;
     }
@@ -184,18 +281,18 @@ mod construct_runtime {
         type Currency = Balances;
         type RuntimeEvent = RuntimeEvent;
         type RuntimeCall = RuntimeCall;
-        type CallFilter = ();
+        type CallFilter = $crate::runtime::call_filter::SandboxCallFilter;
         type WeightPrice = Self;
         type WeightInfo = ();
         type ChainExtension = $chain_extension;
         type Schedule = SandboxSchedule;
-        type CallStack = [$crate::pallet_contracts::Frame<Self>; 5];
-        type DepositPerByte = ConstU128<1>;
-        type DepositPerItem = ConstU128<1>;
+        type CallStack = [$crate::pallet_contracts::Frame<Self>; CALL_STACK_DEPTH];
+        type DepositPerByte = DepositPerByte;
+        type DepositPerItem = DepositPerItem;
         type AddressGenerator = $crate::pallet_contracts::DefaultAddressGenerator;
-        type MaxCodeLen = ConstU32<{ 123 * 1024 }>;
+        type MaxCodeLen = MaxCodeLen;
         type MaxStorageKeyLen = ConstU32<128>;
-        type UnsafeUnstableInterface = ConstBool<false>;
+        type UnsafeUnstableInterface = ConstBool<UNSAFE_UNSTABLE_INTERFACE>;
         type MaxDebugBufferLen = ConstU32<{ 2 * 1024 * 1024 }>;
         type Migrations = ();
         type DefaultDepositLimit = DefaultDepositLimit;
@@ -207,11 +304,18 @@ mod construct_runtime {
         type Xcm = ();
     }
 
-    // ------------ Implement `drink::Runtime` trait ---------------------------------------------------
+    // ------------ Configure additional pallets ---------------------------------------------------
+    $( $( $config )* )?
 
-    /// Default initial balance for the default account.
-    pub const INITIAL_BALANCE: u128 = 1_000_000_000_000_000;
-    $crate::impl_sandbox_config!($name, $name, INITIAL_BALANCE, AccountId32::new([1u8; 32]));
+    // ------------ Implement `drink::Runtime` trait ---------------------------------------------------
+    $crate::impl_sandbox_config!(
+        $name,
+        $name,
+        INITIAL_BALANCE,
+        AccountId32::new([1u8; 32]),
+        extra_pallets: ( $( $( $pallet_name, )* )? ),
+        genesis: { $( $( $genesis ),* )? }
+    );
 }
 
 
@@ -220,9 +324,104 @@ mod construct_runtime {
 // ------------ Export runtime type itself, pallets and useful types from the auxiliary module -----
 pub use construct_runtime::{
     $name, Balances, Contracts, PalletInfo, RuntimeCall, RuntimeEvent, RuntimeHoldReason,
-    RuntimeOrigin, System, Timestamp,
+    RuntimeOrigin, System, Timestamp, $( $( $pallet_name, )* )?
 };
     };
 }
 
 create_minimal_runtime!(MinimalRuntime);
+
+#[cfg(test)]
+mod tests {
+    use crate::Sandbox;
+
+    /// A pallet counting the blocks it has seen, with a value set at genesis.
+    #[frame_support::pallet]
+    pub mod test_pallet {
+        use frame_support::pallet_prelude::*;
+        use frame_system::pallet_prelude::BlockNumberFor;
+
+        #[pallet::pallet]
+        pub struct Pallet<T>(_);
+
+        #[pallet::config]
+        pub trait Config: frame_system::Config {}
+
+        #[pallet::storage]
+        pub type Initialized<T> = StorageValue<_, u32, ValueQuery>;
+
+        #[pallet::storage]
+        pub type Finalized<T> = StorageValue<_, u32, ValueQuery>;
+
+        #[pallet::storage]
+        pub type Magic<T> = StorageValue<_, u32, ValueQuery>;
+
+        #[pallet::genesis_config]
+        #[derive(frame_support::DefaultNoBound)]
+        pub struct GenesisConfig<T: Config> {
+            pub magic: u32,
+            #[serde(skip)]
+            pub _config: PhantomData<T>,
+        }
+
+        #[pallet::genesis_build]
+        impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+            fn build(&self) {
+                Magic::<T>::put(self.magic);
+            }
+        }
+
+        #[pallet::call]
+        impl<T: Config> Pallet<T> {}
+
+        #[pallet::hooks]
+        impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+            fn on_initialize(_: BlockNumberFor<T>) -> Weight {
+                Initialized::<T>::mutate(|count| *count += 1);
+                Weight::zero()
+            }
+
+            fn on_finalize(_: BlockNumberFor<T>) {
+                Finalized::<T>::mutate(|count| *count += 1);
+            }
+        }
+    }
+
+    crate::create_minimal_runtime!(
+        RuntimeWithPallet,
+        (),
+        pallets: { TestPallet: crate::runtime::minimal::tests::test_pallet },
+        configs: {
+            impl crate::runtime::minimal::tests::test_pallet::Config for RuntimeWithPallet {}
+        },
+        genesis: {
+            crate::runtime::minimal::tests::test_pallet::GenesisConfig::<RuntimeWithPallet> {
+                magic: 42,
+                _config: Default::default(),
+            },
+        },
+    );
+
+    #[test]
+    fn extra_pallet_genesis_and_hooks_take_effect() {
+        let mut sandbox = Sandbox::<RuntimeWithPallet>::new().unwrap();
+        let counters = |sandbox: &mut Sandbox<RuntimeWithPallet>| {
+            sandbox.execute_with(|| {
+                (
+                    test_pallet::Initialized::<RuntimeWithPallet>::get(),
+                    test_pallet::Finalized::<RuntimeWithPallet>::get(),
+                )
+            })
+        };
+
+        assert_eq!(
+            sandbox.execute_with(test_pallet::Magic::<RuntimeWithPallet>::get),
+            42
+        );
+        // The first block is initialized when the sandbox is created.
+        assert_eq!(counters(&mut sandbox), (1, 0));
+
+        sandbox.build_blocks(2).unwrap();
+        assert_eq!(counters(&mut sandbox), (3, 2));
+    }
+}
@@ -0,0 +1,226 @@
+//! Ready-made runtimes resembling well-known contract chains, so that the deposit numbers and
+//! limits observed in tests are closer to the production ones.
+//!
+//! Every preset matches the following values of its chain:
+//! - balance decimals, existential deposit and initial balance of the default actor,
+//! - storage deposit prices (`DepositPerByte`, `DepositPerItem` and `DefaultDepositLimit`),
+//! - depth of the contract call stack (unless it is too deep for the default `Schedule`) and
+//!   maximum length of the contract code (`MaxCodeLen`),
+//! - whether the unstable host functions are available.
+//!
+//! All presets use the default `Schedule`, like the chains themselves. Weights, pallets other than
+//! the ones of `MinimalRuntime` and chain extensions are not reproduced.
+//!
+//! The values approximate the respective chains at the time of writing and are not kept in sync
+//! with them. If you need exact numbers, check them against the chain you deploy to and adjust them
+//! with the `contracts_config_api` (or create your own runtime with `create_minimal_runtime!`).
+//!
+//! Every runtime implements `SandboxConfig`, so it can be selected with e.g.
+//! `#[drink::test(config = drink::runtime::presets::AstarLike)]`.
+
+pub mod astar_like {
+    //! Runtime resembling Astar (18 decimals).
+
+    /// One ASTR in the smallest units.
+    pub const UNIT: u128 = 1_000_000_000_000_000_000;
+    /// One thousandth of ASTR.
+    pub const MILLI_UNIT: u128 = UNIT / 1_000;
+    /// One millionth of ASTR.
+    pub const MICRO_UNIT: u128 = MILLI_UNIT / 1_000;
+
+    /// Storage deposit for `items` items and `bytes` bytes of contract storage.
+    pub const fn deposit(items: u32, bytes: u32) -> u128 {
+        items as u128 * 400 * MILLI_UNIT + bytes as u128 * 2_000 * MICRO_UNIT
+    }
+
+    /// Maximum length of the contract code (in bytes).
+    pub const MAX_CODE_LEN: u32 = 123 * 1024;
+
+    crate::create_minimal_runtime!(
+        AstarLike,
+        (),
+        call_stack_depth: 5,
+        parameters: {
+            existential_deposit: 1_000_000,
+            deposit_per_byte: super::deposit(0, 1),
+            deposit_per_item: super::deposit(1, 0),
+            default_deposit_limit: super::deposit(16, 16 * 1024),
+            initial_balance: 1_000_000 * super::UNIT,
+        },
+        genesis: {
+            crate::contracts_config_api::ContractsParametersGenesis::default().with(
+                crate::contracts_config_api::ContractsParameter::MaxCodeLen,
+                super::MAX_CODE_LEN,
+            ),
+        },
+    );
+}
+
+pub mod aleph_zero_like {
+    //! Runtime resembling Aleph Zero (12 decimals).
+    //!
+    //! Aleph Zero allows a deeper contract call stack, which `pallet_contracts` rejects (in its
+    //! integrity test) together with the runtime memory of the default `Schedule`. The preset
+    //! keeps the default depth of 5 instead.
+
+    /// One AZERO in the smallest units.
+    pub const UNIT: u128 = 1_000_000_000_000;
+
+    /// Storage deposit for a single byte of contract storage.
+    pub const DEPOSIT_PER_BYTE: u128 = 4 * (UNIT / 100_000);
+
+    /// Maximum length of the contract code (in bytes).
+    pub const MAX_CODE_LEN: u32 = 256 * 1024;
+
+    crate::create_minimal_runtime!(
+        AlephZeroLike,
+        (),
+        call_stack_depth: 5,
+        parameters: {
+            existential_deposit: 500,
+            deposit_per_byte: super::DEPOSIT_PER_BYTE,
+            deposit_per_item: 32 * super::DEPOSIT_PER_BYTE,
+            default_deposit_limit: 10_000 * super::UNIT,
+            initial_balance: 1_000_000 * super::UNIT,
+        },
+        genesis: {
+            crate::contracts_config_api::ContractsParametersGenesis::default().with(
+                crate::contracts_config_api::ContractsParameter::MaxCodeLen,
+                super::MAX_CODE_LEN,
+            ),
+        },
+    );
+}
+
+pub mod rococo_contracts_like {
+    //! Runtime resembling the Rococo contracts parachain (12 decimals).
+
+    /// One ROC in the smallest units.
+    pub const UNIT: u128 = 1_000_000_000_000;
+    /// One thousandth of ROC.
+    pub const MILLI_UNIT: u128 = UNIT / 1_000;
+
+    /// Storage deposit for `items` items and `bytes` bytes of contract storage.
+    pub const fn deposit(items: u32, bytes: u32) -> u128 {
+        (items as u128 * UNIT + bytes as u128 * (5 * MILLI_UNIT / 100)) / 10
+    }
+
+    /// Maximum length of the contract code (in bytes).
+    pub const MAX_CODE_LEN: u32 = 123 * 1024;
+
+    crate::create_minimal_runtime!(
+        RococoContractsLike,
+        (),
+        call_stack_depth: 5,
+        unsafe_unstable_interface: true,
+        parameters: {
+            existential_deposit: super::MILLI_UNIT,
+            deposit_per_byte: super::deposit(0, 1),
+            deposit_per_item: super::deposit(1, 0),
+            default_deposit_limit: super::deposit(1024, 1024 * 1024),
+            initial_balance: 1_000_000 * super::UNIT,
+        },
+        genesis: {
+            crate::contracts_config_api::ContractsParametersGenesis::default().with(
+                crate::contracts_config_api::ContractsParameter::MaxCodeLen,
+                super::MAX_CODE_LEN,
+            ),
+        },
+    );
+}
+
+/// Runtime resembling Aleph Zero, see `aleph_zero_like`.
+pub use aleph_zero_like::AlephZeroLike;
+/// Runtime resembling Astar, see `astar_like`.
+pub use astar_like::AstarLike;
+/// Runtime resembling the Rococo contracts parachain, see `rococo_contracts_like`.
+pub use rococo_contracts_like::RococoContractsLike;
+
+#[cfg(test)]
+mod tests {
+    use frame_support::traits::Get;
+
+    use super::*;
+    use crate::{Sandbox, SandboxConfig};
+
+    /// Number of frames in the contract call stack of the runtime.
+    fn call_stack_depth<R: pallet_contracts::Config>() -> usize {
+        std::mem::size_of::<R::CallStack>() / std::mem::size_of::<pallet_contracts::Frame<R>>()
+    }
+
+    fn unstable_interface<R: pallet_contracts::Config>() -> bool {
+        R::UnsafeUnstableInterface::get()
+    }
+
+    #[test]
+    fn astar_like_values_take_effect() {
+        let mut sandbox = Sandbox::<AstarLike>::new().unwrap();
+        assert_eq!(
+            sandbox.free_balance(&AstarLike::default_actor()),
+            1_000_000 * astar_like::UNIT
+        );
+        assert_eq!(sandbox.existential_deposit(), 1_000_000);
+        assert_eq!(sandbox.deposit_per_byte(), astar_like::deposit(0, 1));
+        assert_eq!(sandbox.deposit_per_item(), astar_like::deposit(1, 0));
+        assert_eq!(
+            sandbox.default_deposit_limit(),
+            astar_like::deposit(16, 16 * 1024)
+        );
+        assert_eq!(sandbox.max_code_len(), astar_like::MAX_CODE_LEN);
+        assert_eq!(call_stack_depth::<AstarLike>(), 5);
+        assert!(!unstable_interface::<AstarLike>());
+    }
+
+    #[test]
+    fn aleph_zero_like_values_take_effect() {
+        let mut sandbox = Sandbox::<AlephZeroLike>::new().unwrap();
+        assert_eq!(
+            sandbox.free_balance(&AlephZeroLike::default_actor()),
+            1_000_000 * aleph_zero_like::UNIT
+        );
+        assert_eq!(sandbox.existential_deposit(), 500);
+        assert_eq!(
+            sandbox.deposit_per_byte(),
+            aleph_zero_like::DEPOSIT_PER_BYTE
+        );
+        assert_eq!(
+            sandbox.deposit_per_item(),
+            32 * aleph_zero_like::DEPOSIT_PER_BYTE
+        );
+        assert_eq!(
+            sandbox.default_deposit_limit(),
+            10_000 * aleph_zero_like::UNIT
+        );
+        assert_eq!(sandbox.max_code_len(), aleph_zero_like::MAX_CODE_LEN);
+        assert_eq!(call_stack_depth::<AlephZeroLike>(), 5);
+        assert!(!unstable_interface::<AlephZeroLike>());
+    }
+
+    #[test]
+    fn rococo_contracts_like_values_take_effect() {
+        let mut sandbox = Sandbox::<RococoContractsLike>::new().unwrap();
+        assert_eq!(
+            sandbox.free_balance(&RococoContractsLike::default_actor()),
+            1_000_000 * rococo_contracts_like::UNIT
+        );
+        assert_eq!(
+            sandbox.existential_deposit(),
+            rococo_contracts_like::MILLI_UNIT
+        );
+        assert_eq!(
+            sandbox.deposit_per_byte(),
+            rococo_contracts_like::deposit(0, 1)
+        );
+        assert_eq!(
+            sandbox.deposit_per_item(),
+            rococo_contracts_like::deposit(1, 0)
+        );
+        assert_eq!(
+            sandbox.default_deposit_limit(),
+            rococo_contracts_like::deposit(1024, 1024 * 1024)
+        );
+        assert_eq!(sandbox.max_code_len(), rococo_contracts_like::MAX_CODE_LEN);
+        assert_eq!(call_stack_depth::<RococoContractsLike>(), 5);
+        assert!(unstable_interface::<RococoContractsLike>());
+    }
+}
@@ -4,6 +4,8 @@ mod sandbox_config;
 pub use sandbox_config::SandboxConfig;
 pub mod balance_api;
 pub mod contracts_api;
+pub mod contracts_config_api;
+pub mod randomness_api;
 pub mod runtime_api;
 pub mod system_api;
 pub mod timestamp_api;
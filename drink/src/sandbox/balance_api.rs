@@ -1,9 +1,19 @@
 //! Balance API for the sandbox.
-use frame_support::{sp_runtime::DispatchError, traits::fungible::Mutate};
+use frame_support::{
+    sp_runtime::DispatchError,
+    traits::{
+        fungible::{Inspect, InspectHold, Mutate},
+        tokens::{Fortitude, Precision, Preservation},
+        Get,
+    },
+};
 
 use super::Sandbox;
 use crate::{runtime::AccountIdFor, BalanceOf, SandboxConfig};
 
+/// Alias for `pallet-balances`'s hold reason type.
+pub type RuntimeHoldReasonOf<R> = <R as pallet_balances::Config>::RuntimeHoldReason;
+
 impl<Config: SandboxConfig> Sandbox<Config>
 where
     Config::Runtime: pallet_balances::Config,
@@ -24,6 +34,53 @@ where
         })
     }
 
+    /// Burn tokens from an account. Returns the number of burnt tokens.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address of the account to remove tokens from.
+    /// * `amount` - The number of tokens to burn. Fails if the account cannot afford it.
+    pub fn burn_from(
+        &mut self,
+        address: &AccountIdFor<Config::Runtime>,
+        amount: BalanceOf<Config::Runtime>,
+    ) -> Result<BalanceOf<Config::Runtime>, DispatchError> {
+        self.execute_with(|| {
+            pallet_balances::Pallet::<Config::Runtime>::burn_from(
+                address,
+                amount,
+                Precision::Exact,
+                Fortitude::Polite,
+            )
+        })
+    }
+
+    /// Transfer tokens between accounts. Returns the number of transferred tokens.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The address of the account to take tokens from.
+    /// * `dest` - The address of the account to give tokens to.
+    /// * `amount` - The number of tokens to transfer.
+    /// * `preservation` - Whether the source account may be reaped by the transfer
+    ///   (`Preservation::Expendable`) or has to be kept alive.
+    pub fn transfer(
+        &mut self,
+        source: &AccountIdFor<Config::Runtime>,
+        dest: &AccountIdFor<Config::Runtime>,
+        amount: BalanceOf<Config::Runtime>,
+        preservation: Preservation,
+    ) -> Result<BalanceOf<Config::Runtime>, DispatchError> {
+        self.execute_with(|| {
+            <pallet_balances::Pallet<Config::Runtime> as Mutate<_>>::transfer(
+                source,
+                dest,
+                amount,
+                preservation,
+            )
+        })
+    }
+
     /// Return the free balance of an account.
     ///
     /// # Arguments
@@ -35,4 +92,205 @@ where
     ) -> BalanceOf<Config::Runtime> {
         self.execute_with(|| pallet_balances::Pallet::<Config::Runtime>::free_balance(address))
     }
+
+    /// Return the reserved balance of an account, including all the holds.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address of the account to query.
+    pub fn reserved_balance(
+        &mut self,
+        address: &AccountIdFor<Config::Runtime>,
+    ) -> BalanceOf<Config::Runtime> {
+        self.execute_with(|| pallet_balances::Pallet::<Config::Runtime>::reserved_balance(address))
+    }
+
+    /// Return the balance of an account held for a particular reason.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - The hold reason, e.g. `pallet_contracts::HoldReason::StorageDepositReserve`.
+    /// * `address` - The address of the account to query.
+    pub fn balance_on_hold(
+        &mut self,
+        reason: impl Into<RuntimeHoldReasonOf<Config::Runtime>>,
+        address: &AccountIdFor<Config::Runtime>,
+    ) -> BalanceOf<Config::Runtime> {
+        let reason = reason.into();
+        self.execute_with(|| {
+            pallet_balances::Pallet::<Config::Runtime>::balance_on_hold(&reason, address)
+        })
+    }
+
+    /// Return the total balance of an account held for all reasons.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address of the account to query.
+    pub fn total_balance_on_hold(
+        &mut self,
+        address: &AccountIdFor<Config::Runtime>,
+    ) -> BalanceOf<Config::Runtime> {
+        self.execute_with(|| {
+            pallet_balances::Pallet::<Config::Runtime>::total_balance_on_hold(address)
+        })
+    }
+
+    /// Return the total balance (free and reserved) of an account.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address of the account to query.
+    pub fn total_balance(
+        &mut self,
+        address: &AccountIdFor<Config::Runtime>,
+    ) -> BalanceOf<Config::Runtime> {
+        self.execute_with(|| pallet_balances::Pallet::<Config::Runtime>::total_balance(address))
+    }
+
+    /// Return the balance of an account that can be transferred or burnt.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address of the account to query.
+    /// * `preservation` - Whether the account has to be kept alive (above the existential
+    ///   deposit) afterwards.
+    pub fn reducible_balance(
+        &mut self,
+        address: &AccountIdFor<Config::Runtime>,
+        preservation: Preservation,
+    ) -> BalanceOf<Config::Runtime> {
+        self.execute_with(|| {
+            pallet_balances::Pallet::<Config::Runtime>::reducible_balance(
+                address,
+                preservation,
+                Fortitude::Polite,
+            )
+        })
+    }
+
+    /// Return the total issuance of the native token.
+    pub fn total_issuance(&mut self) -> BalanceOf<Config::Runtime> {
+        self.execute_with(pallet_balances::Pallet::<Config::Runtime>::total_issuance)
+    }
+
+    /// Return the existential deposit, i.e. the minimum balance of an account to be kept alive.
+    pub fn existential_deposit(&mut self) -> BalanceOf<Config::Runtime> {
+        self.execute_with(<Config::Runtime as pallet_balances::Config>::ExistentialDeposit::get)
+    }
+
+    /// Return whether the account exists, i.e. it has not been reaped (or never existed).
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address of the account to query.
+    pub fn account_exists(&mut self, address: &AccountIdFor<Config::Runtime>) -> bool {
+        self.execute_with(|| frame_system::Pallet::<Config::Runtime>::account_exists(address))
+    }
+}
+
+impl<Config: SandboxConfig> Sandbox<Config>
+where
+    Config::Runtime: pallet_balances::Config + pallet_contracts::Config,
+    RuntimeHoldReasonOf<Config::Runtime>: From<pallet_contracts::HoldReason>,
+{
+    /// Return the balance of an account held by `pallet-contracts` as a storage deposit.
+    ///
+    /// For a contract, it is the deposit for its storage items. The deposit for uploaded code is
+    /// held under `pallet_contracts::HoldReason::CodeUploadDepositReserve` instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address of the account to query.
+    pub fn storage_deposit_on_hold(
+        &mut self,
+        address: &AccountIdFor<Config::Runtime>,
+    ) -> BalanceOf<Config::Runtime> {
+        self.balance_on_hold(pallet_contracts::HoldReason::StorageDepositReserve, address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use frame_support::traits::{fungible::MutateHold, tokens::Preservation};
+
+    use crate::{
+        runtime::{minimal::RuntimeHoldReason, MinimalRuntime},
+        AccountId32, Sandbox, SandboxConfig,
+    };
+
+    const RECIPIENT: AccountId32 = AccountId32::new([2u8; 32]);
+
+    #[test]
+    fn transfer_works() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().expect("Failed to create sandbox");
+        let actor = MinimalRuntime::default_actor();
+        let initial = sandbox.free_balance(&actor);
+
+        sandbox
+            .transfer(&actor, &RECIPIENT, 100, Preservation::Preserve)
+            .expect("Failed to transfer");
+
+        assert_eq!(sandbox.free_balance(&actor), initial - 100);
+        assert_eq!(sandbox.free_balance(&RECIPIENT), 100);
+    }
+
+    #[test]
+    fn expendable_transfer_reaps_account() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().expect("Failed to create sandbox");
+        let actor = MinimalRuntime::default_actor();
+        sandbox.mint_into(RECIPIENT, 100).unwrap();
+        assert!(sandbox.account_exists(&RECIPIENT));
+
+        assert!(sandbox
+            .transfer(&RECIPIENT, &actor, 100, Preservation::Preserve)
+            .is_err());
+        assert_eq!(
+            sandbox.reducible_balance(&RECIPIENT, Preservation::Preserve),
+            100 - sandbox.existential_deposit()
+        );
+
+        sandbox
+            .transfer(&RECIPIENT, &actor, 100, Preservation::Expendable)
+            .expect("Failed to transfer");
+        assert!(!sandbox.account_exists(&RECIPIENT));
+    }
+
+    #[test]
+    fn burning_reduces_total_issuance() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().expect("Failed to create sandbox");
+        let actor = MinimalRuntime::default_actor();
+        let issuance = sandbox.total_issuance();
+
+        assert_eq!(sandbox.burn_from(&actor, 1_000), Ok(1_000));
+        assert_eq!(sandbox.total_issuance(), issuance - 1_000);
+    }
+
+    #[test]
+    fn held_balance_is_reported() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().expect("Failed to create sandbox");
+        sandbox.mint_into(RECIPIENT, 1_000).unwrap();
+
+        sandbox.execute_with(|| {
+            pallet_balances::Pallet::<MinimalRuntime>::hold(
+                &RuntimeHoldReason::Contracts(pallet_contracts::HoldReason::StorageDepositReserve),
+                &RECIPIENT,
+                300,
+            )
+            .expect("Failed to hold")
+        });
+
+        assert_eq!(sandbox.storage_deposit_on_hold(&RECIPIENT), 300);
+        assert_eq!(
+            sandbox.balance_on_hold(
+                pallet_contracts::HoldReason::CodeUploadDepositReserve,
+                &RECIPIENT
+            ),
+            0
+        );
+        assert_eq!(sandbox.total_balance_on_hold(&RECIPIENT), 300);
+        assert_eq!(sandbox.reserved_balance(&RECIPIENT), 300);
+        assert_eq!(sandbox.free_balance(&RECIPIENT), 700);
+        assert_eq!(sandbox.total_balance(&RECIPIENT), 1_000);
+    }
 }
@@ -1,13 +1,17 @@
 //! Contracts API for the sandbox.
-use std::ops::Not;
+use std::{fmt::Debug, ops::Not};
 
-use frame_support::{traits::fungible::Inspect, weights::Weight};
+use frame_support::{
+    dispatch::DispatchResult, sp_runtime::traits::StaticLookup, traits::fungible::Inspect,
+    weights::Weight,
+};
 use frame_system::Config as SysConfig;
 use pallet_contracts::{
-    Code, CodeUploadResult, CollectEvents, ContractExecResult, ContractInstantiateResult,
-    DebugInfo, Determinism,
+    AddressGenerator, Code, CodeUploadResult, CollectEvents, ContractExecResult,
+    ContractInstantiateResult, DebugInfo, Determinism, GetStorageResult,
 };
-use parity_scale_codec::Decode as _;
+use parity_scale_codec::{Decode as _, Encode, HasCompact};
+use scale_info::TypeInfo;
 
 use crate::{runtime::AccountIdFor, EventRecordOf, Sandbox};
 
@@ -164,6 +168,105 @@ where
             )
         })
     }
+
+    /// Replace the code of a contract, like `pallet_contracts::Call::set_code` dispatched by root.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address of the contract to be upgraded.
+    /// * `code_hash` - The hash of the new (already uploaded) code.
+    pub fn set_contract_code(
+        &mut self,
+        address: AccountIdFor<Config::Runtime>,
+        code_hash: <Config::Runtime as SysConfig>::Hash,
+    ) -> DispatchResult
+    where
+        <BalanceOf<Config::Runtime> as HasCompact>::Type: Clone + Eq + Debug + TypeInfo + Encode,
+    {
+        self.externalities.execute_with(|| {
+            pallet_contracts::Pallet::<Config::Runtime>::set_code(
+                frame_system::RawOrigin::Root.into(),
+                <Config::Runtime as SysConfig>::Lookup::unlookup(address),
+                code_hash,
+            )
+        })
+    }
+
+    /// Remove an uploaded code, which is not used by any contract, and refund its deposit.
+    ///
+    /// # Arguments
+    ///
+    /// * `code_hash` - The hash of the code to be removed.
+    /// * `origin` - The account that uploaded the code.
+    pub fn remove_code(
+        &mut self,
+        code_hash: <Config::Runtime as SysConfig>::Hash,
+        origin: AccountIdFor<Config::Runtime>,
+    ) -> DispatchResult
+    where
+        <BalanceOf<Config::Runtime> as HasCompact>::Type: Clone + Eq + Debug + TypeInfo + Encode,
+    {
+        self.externalities.execute_with(|| {
+            pallet_contracts::Pallet::<Config::Runtime>::remove_code(
+                frame_system::RawOrigin::Signed(origin).into(),
+                code_hash,
+            )
+            .map(|_| ())
+            .map_err(|err| err.error)
+        })
+    }
+
+    /// Return the code hash of the contract at `address`, or `None` if there is no contract
+    /// (e.g. it has been terminated).
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address of the contract.
+    pub fn contract_code_hash(
+        &mut self,
+        address: &AccountIdFor<Config::Runtime>,
+    ) -> Option<<Config::Runtime as SysConfig>::Hash> {
+        self.externalities
+            .execute_with(|| pallet_contracts::Pallet::<Config::Runtime>::code_hash(address))
+    }
+
+    /// Compute the address of a contract that would be instantiated with the given parameters,
+    /// using the `AddressGenerator` of the runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `deployer` - The account instantiating the contract.
+    /// * `code_hash` - The hash of the contract code.
+    /// * `input` - The input data passed to the constructor (including constructor selector).
+    /// * `salt` - The salt used for contract address derivation.
+    pub fn predict_contract_address(
+        &mut self,
+        deployer: &AccountIdFor<Config::Runtime>,
+        code_hash: &<Config::Runtime as SysConfig>::Hash,
+        input: &[u8],
+        salt: &[u8],
+    ) -> AccountIdFor<Config::Runtime> {
+        self.externalities.execute_with(|| {
+            <<Config::Runtime as pallet_contracts::Config>::AddressGenerator as AddressGenerator<
+                Config::Runtime,
+            >>::contract_address(deployer, code_hash, input, salt)
+        })
+    }
+
+    /// Read a raw value from the storage of a contract.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address of the contract.
+    /// * `key` - The storage key, as used by the contract.
+    pub fn contract_storage(
+        &mut self,
+        address: AccountIdFor<Config::Runtime>,
+        key: Vec<u8>,
+    ) -> GetStorageResult {
+        self.externalities
+            .execute_with(|| pallet_contracts::Pallet::<Config::Runtime>::get_storage(address, key))
+    }
 }
 
 /// Converts bytes to a '\n'-split string, ignoring empty lines.
@@ -306,4 +409,31 @@ mod tests {
             }),
         );
     }
+
+    #[test]
+    fn predicted_address_matches_deployed_one() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().unwrap();
+        let actor = MinimalRuntime::default_actor();
+        let wasm_binary = compile_module("dummy");
+        let hash = <<MinimalRuntime as frame_system::Config>::Hashing>::hash(&wasm_binary);
+
+        let predicted = sandbox.predict_contract_address(&actor, &hash, &[], &[7]);
+
+        let result = sandbox.deploy_contract(
+            wasm_binary,
+            0,
+            vec![],
+            vec![7],
+            actor,
+            DEFAULT_GAS_LIMIT,
+            None,
+        );
+        assert_eq!(
+            result
+                .result
+                .expect("Contract should be deployed")
+                .account_id,
+            predicted
+        );
+    }
 }
@@ -0,0 +1,203 @@
+//! API for tuning the `pallet_contracts` configuration of the sandbox.
+
+use frame_support::{
+    sp_runtime::{BuildStorage, Storage},
+    storage::unhashed,
+    traits::{fungible::Inspect, Get},
+};
+use pallet_contracts::Schedule;
+use parity_scale_codec::{Decode, Encode};
+
+use crate::{runtime::AccountIdFor, Sandbox, SandboxConfig};
+
+type BalanceOf<R> =
+    <<R as pallet_contracts::Config>::Currency as Inspect<AccountIdFor<R>>>::Balance;
+
+/// A `pallet_contracts` parameter that can be overridden in a running sandbox.
+///
+/// The overrides are read (with [`parameter`]) by the runtimes created with
+/// `create_minimal_runtime!`. Other runtimes have to read them on their own to be tunable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContractsParameter {
+    /// `pallet_contracts::Config::Schedule`.
+    Schedule,
+    /// `pallet_contracts::Config::DepositPerByte`.
+    DepositPerByte,
+    /// `pallet_contracts::Config::DepositPerItem`.
+    DepositPerItem,
+    /// `pallet_contracts::Config::DefaultDepositLimit`.
+    DefaultDepositLimit,
+    /// `pallet_contracts::Config::MaxCodeLen`.
+    MaxCodeLen,
+}
+
+impl ContractsParameter {
+    /// Storage key under which the override of the parameter is kept.
+    fn key(self) -> &'static [u8] {
+        match self {
+            Self::Schedule => b":drink:contracts_schedule",
+            Self::DepositPerByte => b":drink:deposit_per_byte",
+            Self::DepositPerItem => b":drink:deposit_per_item",
+            Self::DefaultDepositLimit => b":drink:default_deposit_limit",
+            Self::MaxCodeLen => b":drink:max_code_len",
+        }
+    }
+}
+
+/// Returns the value of `parameter` set for the sandbox, or `default()` if it hasn't been
+/// overridden.
+///
+/// Outside of the externalities (e.g. when the runtime metadata is built from the pallet
+/// constants), `default()` is returned.
+pub fn parameter<T: Decode>(parameter: ContractsParameter, default: impl FnOnce() -> T) -> T {
+    sp_externalities::with_externalities(|ext| ext.storage(parameter.key()))
+        .flatten()
+        .and_then(|value| T::decode(&mut &value[..]).ok())
+        .unwrap_or_else(default)
+}
+
+/// Overrides of `pallet_contracts` parameters to be put into the initial storage of a sandbox,
+/// e.g. within the `genesis` section of `create_minimal_runtime!`.
+#[derive(Clone, Debug, Default)]
+pub struct ContractsParametersGenesis {
+    overrides: Vec<(ContractsParameter, Vec<u8>)>,
+}
+
+impl ContractsParametersGenesis {
+    /// Overrides `parameter` with `value`. The type of `value` must match the one of the parameter
+    /// (e.g. `u32` for `ContractsParameter::MaxCodeLen`).
+    pub fn with(mut self, parameter: ContractsParameter, value: impl Encode) -> Self {
+        self.overrides.push((parameter, value.encode()));
+        self
+    }
+}
+
+impl BuildStorage for ContractsParametersGenesis {
+    fn assimilate_storage(&self, storage: &mut Storage) -> Result<(), String> {
+        for (parameter, value) in &self.overrides {
+            storage.top.insert(parameter.key().to_vec(), value.clone());
+        }
+        Ok(())
+    }
+}
+
+impl<Config: SandboxConfig> Sandbox<Config>
+where
+    Config::Runtime: pallet_contracts::Config,
+{
+    /// Returns the schedule used by the contracts pallet.
+    pub fn schedule(&mut self) -> Schedule<Config::Runtime> {
+        self.execute_with(<Config::Runtime as pallet_contracts::Config>::Schedule::get)
+    }
+
+    /// Overrides the schedule used by the contracts pallet.
+    ///
+    /// # Arguments
+    ///
+    /// * `schedule` - The new schedule (costs and limits of the contract execution).
+    pub fn set_schedule(&mut self, schedule: Schedule<Config::Runtime>) {
+        self.set_parameter(ContractsParameter::Schedule, &schedule);
+    }
+
+    /// Returns the deposit charged for every byte of contract storage.
+    pub fn deposit_per_byte(&mut self) -> BalanceOf<Config::Runtime> {
+        self.execute_with(<Config::Runtime as pallet_contracts::Config>::DepositPerByte::get)
+    }
+
+    /// Overrides the deposit charged for every byte of contract storage.
+    ///
+    /// # Arguments
+    ///
+    /// * `deposit` - The new deposit per byte.
+    pub fn set_deposit_per_byte(&mut self, deposit: BalanceOf<Config::Runtime>) {
+        self.set_parameter(ContractsParameter::DepositPerByte, &deposit);
+    }
+
+    /// Returns the deposit charged for every item of contract storage.
+    pub fn deposit_per_item(&mut self) -> BalanceOf<Config::Runtime> {
+        self.execute_with(<Config::Runtime as pallet_contracts::Config>::DepositPerItem::get)
+    }
+
+    /// Overrides the deposit charged for every item of contract storage.
+    ///
+    /// # Arguments
+    ///
+    /// * `deposit` - The new deposit per item.
+    pub fn set_deposit_per_item(&mut self, deposit: BalanceOf<Config::Runtime>) {
+        self.set_parameter(ContractsParameter::DepositPerItem, &deposit);
+    }
+
+    /// Returns the storage deposit limit used by the runtime API when no limit is given.
+    pub fn default_deposit_limit(&mut self) -> BalanceOf<Config::Runtime> {
+        self.execute_with(<Config::Runtime as pallet_contracts::Config>::DefaultDepositLimit::get)
+    }
+
+    /// Overrides the storage deposit limit used by the runtime API when no limit is given.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The new default deposit limit.
+    pub fn set_default_deposit_limit(&mut self, limit: BalanceOf<Config::Runtime>) {
+        self.set_parameter(ContractsParameter::DefaultDepositLimit, &limit);
+    }
+
+    /// Returns the maximum length of the contract code (in bytes).
+    pub fn max_code_len(&mut self) -> u32 {
+        self.execute_with(<Config::Runtime as pallet_contracts::Config>::MaxCodeLen::get)
+    }
+
+    /// Overrides the maximum length of the contract code (in bytes).
+    ///
+    /// Notice that lowering the limit may make the already uploaded code unusable.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_code_len` - The new limit.
+    pub fn set_max_code_len(&mut self, max_code_len: u32) {
+        self.set_parameter(ContractsParameter::MaxCodeLen, &max_code_len);
+    }
+
+    /// Removes the override of `parameter`, bringing back the default value of the runtime.
+    pub fn reset_contracts_parameter(&mut self, parameter: ContractsParameter) {
+        self.execute_with(|| unhashed::kill(parameter.key()));
+    }
+
+    fn set_parameter(&mut self, parameter: ContractsParameter, value: &impl Encode) {
+        self.execute_with(|| unhashed::put(parameter.key(), value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MinimalRuntime;
+
+    #[test]
+    fn parameters_fall_back_to_defaults_outside_externalities() {
+        assert_eq!(parameter(ContractsParameter::MaxCodeLen, || 7u32), 7);
+        // Metadata is built from the pallet constants, some of which are tunable parameters.
+        MinimalRuntime::get_metadata();
+    }
+
+    #[test]
+    fn parameters_can_be_overridden_and_reset() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().unwrap();
+        let default = sandbox.deposit_per_byte();
+
+        sandbox.set_deposit_per_byte(default + 5);
+        assert_eq!(sandbox.deposit_per_byte(), default + 5);
+
+        sandbox.reset_contracts_parameter(ContractsParameter::DepositPerByte);
+        assert_eq!(sandbox.deposit_per_byte(), default);
+    }
+
+    #[test]
+    fn schedule_can_be_overridden() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().unwrap();
+        let mut schedule = sandbox.schedule();
+        schedule.limits.memory_pages = 1;
+
+        sandbox.set_schedule(schedule.clone());
+        assert_eq!(sandbox.schedule(), schedule);
+    }
+}
@@ -0,0 +1,95 @@
+//! Randomness API for the sandbox.
+
+use frame_support::storage::unhashed;
+use parity_scale_codec::Encode;
+
+use crate::{Sandbox, SandboxConfig};
+
+/// Storage key under which the randomness seed of the sandbox is kept.
+const SEED_KEY: &[u8] = b":drink:randomness_seed";
+/// Storage key under which the forced random outputs are kept.
+const FORCED_KEY: &[u8] = b":drink:forced_randomness";
+
+/// Computes a random output for `subject` at `block_number`.
+///
+/// If some outputs have been forced with `Sandbox::force_next_random`, the oldest one is consumed
+/// and returned. Otherwise, the output is derived deterministically from the sandbox seed, the
+/// subject and the block number (so, just like on a real chain, it is the same for the same
+/// subject within a single block).
+///
+/// Meant to be used by the `Randomness` implementation of a sandbox runtime. Must be called
+/// within the externalities.
+pub fn random(subject: &[u8], block_number: impl Encode) -> [u8; 32] {
+    let mut forced = unhashed::get::<Vec<[u8; 32]>>(FORCED_KEY).unwrap_or_default();
+    if !forced.is_empty() {
+        let output = forced.remove(0);
+        unhashed::put(FORCED_KEY, &forced);
+        return output;
+    }
+
+    let seed = unhashed::get::<u64>(SEED_KEY).unwrap_or_default();
+    sp_io::hashing::blake2_256(&(seed, subject, block_number).encode())
+}
+
+impl<Config: SandboxConfig> Sandbox<Config> {
+    /// Set the seed from which random outputs are derived.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The new seed.
+    pub fn set_randomness_seed(&mut self, seed: u64) {
+        self.execute_with(|| unhashed::put(SEED_KEY, &seed))
+    }
+
+    /// Force the output of the next randomness query. Calling it multiple times queues the
+    /// outputs, which are then consumed in order.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The output to be returned.
+    pub fn force_next_random(&mut self, output: [u8; 32]) {
+        self.execute_with(|| {
+            let mut forced = unhashed::get::<Vec<[u8; 32]>>(FORCED_KEY).unwrap_or_default();
+            forced.push(output);
+            unhashed::put(FORCED_KEY, &forced);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use frame_support::{sp_runtime::testing::H256, traits::Randomness};
+
+    use crate::{runtime::MinimalRuntime, Sandbox};
+
+    type SandboxRandomness = <MinimalRuntime as pallet_contracts::Config>::Randomness;
+
+    fn random(sandbox: &mut Sandbox<MinimalRuntime>, subject: &[u8]) -> H256 {
+        sandbox.execute_with(|| SandboxRandomness::random(subject).0)
+    }
+
+    #[test]
+    fn randomness_is_deterministic() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().expect("Failed to create sandbox");
+        let mut other_sandbox = Sandbox::<MinimalRuntime>::new().expect("Failed to create sandbox");
+
+        assert_eq!(random(&mut sandbox, b"x"), random(&mut other_sandbox, b"x"));
+        assert_ne!(random(&mut sandbox, b"x"), random(&mut sandbox, b"y"));
+
+        other_sandbox.set_randomness_seed(7);
+        assert_ne!(random(&mut sandbox, b"x"), random(&mut other_sandbox, b"x"));
+    }
+
+    #[test]
+    fn forced_randomness_is_consumed_in_order() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().expect("Failed to create sandbox");
+        let regular = random(&mut sandbox, b"x");
+
+        sandbox.force_next_random([1u8; 32]);
+        sandbox.force_next_random([2u8; 32]);
+
+        assert_eq!(random(&mut sandbox, b"x"), H256([1u8; 32]));
+        assert_eq!(random(&mut sandbox, b"x"), H256([2u8; 32]));
+        assert_eq!(random(&mut sandbox, b"x"), regular);
+    }
+}
@@ -1,15 +1,27 @@
 //! Basic chain API.
 
 use frame_support::sp_runtime::{
-    traits::{One, Saturating},
+    traits::{One, Saturating, UniqueSaturatedInto},
     BuildStorage,
 };
 use frame_system::{pallet_prelude::BlockNumberFor, GenesisConfig};
 use sp_io::TestExternalities;
 
-use super::Sandbox;
+use super::{timestamp_api, Sandbox};
 use crate::{DrinkResult, Error};
 
+/// Block hooks to be run when warping to a distant block.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WarpHooks {
+    /// Finalize the current block and initialize the landing block, exactly as `build_block`
+    /// does.
+    #[default]
+    FinalizeAndInitialize,
+    /// Only initialize the landing block. The current block is not finalized (so its
+    /// `on_finalize` hooks are not run) and the landing block gets a default parent hash.
+    LandingBlockOnly,
+}
+
 impl<Config: crate::SandboxConfig> Sandbox<Config> {
     /// Creates a new sandbox.
     ///
@@ -65,4 +77,133 @@ impl<Config: crate::SandboxConfig> Sandbox<Config> {
         }
         Ok(last_block.unwrap_or_else(|| self.block_number()))
     }
+
+    /// Jump directly to the block at `height` and return it.
+    ///
+    /// Unlike `build_blocks`, intermediate blocks are skipped entirely: none of their hooks are
+    /// run, so this is cheap regardless of the distance. With `TimeSource::Linear`, the timestamp
+    /// of the landing block accounts for the skipped blocks, as if they were built.
+    ///
+    /// Returns `Error::InvalidTargetHeight` if `height` is not greater than the current height.
+    ///
+    /// # Arguments
+    ///
+    /// * `height` - The height of the landing block.
+    /// * `hooks` - Which block hooks should be run.
+    /// * `timestamp` - The timestamp of the landing block (in milliseconds). If `None`, it is
+    ///   computed by the configured `TimeSource`.
+    pub fn warp_to_block(
+        &mut self,
+        height: BlockNumberFor<Config::Runtime>,
+        hooks: WarpHooks,
+        timestamp: Option<u64>,
+    ) -> DrinkResult<BlockNumberFor<Config::Runtime>> {
+        self.execute_with(|| {
+            let current_block = frame_system::Pallet::<Config::Runtime>::block_number();
+            if height <= current_block {
+                return Err(Error::InvalidTargetHeight {
+                    target: height.unique_saturated_into(),
+                    current: current_block.unique_saturated_into(),
+                });
+            }
+
+            let parent_hash = match hooks {
+                WarpHooks::FinalizeAndInitialize => {
+                    Config::finalize_block(current_block).map_err(Error::BlockFinalize)?
+                }
+                WarpHooks::LandingBlockOnly => Default::default(),
+            };
+
+            match timestamp {
+                Some(timestamp) => timestamp_api::set_next_block_timestamp(timestamp),
+                None => {
+                    let skipped: u64 =
+                        (height - current_block - One::one()).unique_saturated_into();
+                    timestamp_api::account_for_skipped_blocks(skipped);
+                }
+            }
+
+            Config::initialize_block(height, parent_hash).map_err(Error::BlockInitialize)?;
+            Ok(height)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WarpHooks;
+    use crate::{runtime::MinimalRuntime, timestamp_api::TimeSource, Error, Sandbox};
+
+    #[test]
+    fn warping_to_block_works() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().expect("Failed to create sandbox");
+
+        let height = sandbox
+            .warp_to_block(1_000_000, WarpHooks::FinalizeAndInitialize, None)
+            .expect("Failed to warp");
+        assert_eq!(height, 1_000_000);
+        assert_eq!(sandbox.block_number(), 1_000_000);
+
+        sandbox
+            .warp_to_block(2_000_000, WarpHooks::LandingBlockOnly, None)
+            .expect("Failed to warp");
+        assert_eq!(
+            sandbox.build_block().expect("Failed to build block"),
+            2_000_001
+        );
+    }
+
+    #[test]
+    fn warping_backwards_fails() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().expect("Failed to create sandbox");
+        sandbox.build_blocks(5).expect("Failed to build blocks");
+
+        assert!(matches!(
+            sandbox.warp_to_block(6, WarpHooks::default(), None),
+            Err(Error::InvalidTargetHeight {
+                target: 6,
+                current: 6
+            })
+        ));
+        assert!(matches!(
+            sandbox.warp_to_block(3, WarpHooks::default(), None),
+            Err(Error::InvalidTargetHeight {
+                target: 3,
+                current: 6
+            })
+        ));
+        assert_eq!(sandbox.block_number(), 6);
+    }
+
+    #[test]
+    fn warping_accounts_for_linear_time() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().expect("Failed to create sandbox");
+        sandbox.set_time_source(TimeSource::Linear {
+            genesis: 0,
+            increment: 6_000,
+        });
+
+        sandbox
+            .warp_to_block(11, WarpHooks::default(), None)
+            .expect("Failed to warp");
+        assert_eq!(sandbox.get_timestamp(), 60_000);
+    }
+
+    #[test]
+    fn warping_with_timestamp_sets_it_exactly() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().expect("Failed to create sandbox");
+        sandbox.set_time_source(TimeSource::Linear {
+            genesis: 0,
+            increment: 6_000,
+        });
+
+        sandbox
+            .warp_to_block(100, WarpHooks::default(), Some(1_000_000))
+            .expect("Failed to warp");
+        assert_eq!(sandbox.get_timestamp(), 1_000_000);
+
+        // The next blocks continue from the requested timestamp.
+        sandbox.build_block().expect("Failed to build block");
+        assert_eq!(sandbox.get_timestamp(), 1_006_000);
+    }
 }
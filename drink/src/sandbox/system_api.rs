@@ -1,10 +1,20 @@
 //! System API for the sandbox.
 
-use frame_support::sp_runtime::{traits::Dispatchable, DispatchResultWithInfo};
-use frame_system::pallet_prelude::BlockNumberFor;
+use frame_support::{
+    dispatch::{DispatchInfo, GetDispatchInfo, PostDispatchInfo},
+    sp_runtime::{
+        traits::{Dispatchable, SignedExtension},
+        ApplyExtrinsicResultWithInfo, DispatchResultWithInfo,
+    },
+};
+use frame_system::{pallet_prelude::BlockNumberFor, CheckWeight};
+use parity_scale_codec::Encode;
 
 use super::Sandbox;
-use crate::{EventRecordOf, RuntimeCall, SandboxConfig};
+use crate::{DrinkResult, EventRecordOf, RuntimeCall, SandboxConfig};
+
+/// The origin type of runtime calls.
+type RuntimeOriginOf<R> = <RuntimeCall<R> as Dispatchable>::RuntimeOrigin;
 
 impl<Config: SandboxConfig> Sandbox<Config> {
     /// Return the current height of the chain.
@@ -39,15 +49,93 @@ impl<Config: SandboxConfig> Sandbox<Config> {
     }
 }
 
+impl<Config: SandboxConfig> Sandbox<Config>
+where
+    Config::Runtime: Send + Sync,
+    RuntimeCall<Config::Runtime>:
+        Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo> + GetDispatchInfo,
+{
+    /// Build a new block and apply the given calls in it as extrinsics, in order.
+    ///
+    /// Unlike with `runtime_call`, every call goes through the extrinsic pipeline of
+    /// `frame-system`: it gets the next extrinsic index, its weight is checked against and
+    /// accounted in the block limits, and an `ExtrinsicSuccess` or `ExtrinsicFailed` event is
+    /// emitted. The events emitted during the call are in the `ApplyExtrinsic` phase of its index.
+    ///
+    /// The new block is left open, so that its events can be inspected with `events`.
+    ///
+    /// Returns the results of applying the calls. A call that does not fit in the block (e.g.
+    /// exceeds the remaining block weight) is rejected with a `TransactionValidityError` and not
+    /// included in the block.
+    ///
+    /// # Arguments
+    ///
+    /// * `calls` - The runtime calls to apply, together with their origins.
+    pub fn build_block_with<Origin: Into<RuntimeOriginOf<Config::Runtime>>>(
+        &mut self,
+        calls: Vec<(RuntimeCall<Config::Runtime>, Origin)>,
+    ) -> DrinkResult<Vec<ApplyExtrinsicResultWithInfo<PostDispatchInfo>>> {
+        self.build_block()?;
+        Ok(self.execute_with(|| {
+            calls
+                .into_iter()
+                .map(|(call, origin)| apply_extrinsic::<Config::Runtime>(call, origin.into()))
+                .collect()
+        }))
+    }
+}
+
+/// Applies `call` as an extrinsic of the current block, mimicking `frame-executive`.
+fn apply_extrinsic<R: frame_system::Config + Send + Sync>(
+    call: RuntimeCall<R>,
+    origin: RuntimeOriginOf<R>,
+) -> ApplyExtrinsicResultWithInfo<PostDispatchInfo>
+where
+    RuntimeCall<R>:
+        Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo> + GetDispatchInfo,
+{
+    let info = call.get_dispatch_info();
+    let len = call.encoded_size();
+
+    CheckWeight::<R>::do_pre_dispatch(&info, len)?;
+    frame_system::Pallet::<R>::note_extrinsic(call.encode());
+
+    let result = call.dispatch(origin);
+    let post_info = match &result {
+        Ok(post_info) => *post_info,
+        Err(err) => err.post_info,
+    };
+    CheckWeight::<R>::post_dispatch(
+        Some(()),
+        &info,
+        &post_info,
+        len,
+        &result.map(|_| ()).map_err(|err| err.error),
+    )?;
+
+    frame_system::Pallet::<R>::note_applied_extrinsic(&result, info);
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
     use frame_support::sp_runtime::{traits::Dispatchable, DispatchResultWithInfo};
+    use frame_system::Phase;
 
     use crate::{
         runtime::{minimal::RuntimeEvent, MinimalRuntime},
         AccountId32, RuntimeCall, Sandbox, SandboxConfig,
     };
 
+    fn transfer_call(dest: AccountId32, value: u128) -> RuntimeCall<MinimalRuntime> {
+        RuntimeCall::<MinimalRuntime>::Balances(
+            pallet_balances::Call::<MinimalRuntime>::transfer_allow_death {
+                dest: dest.into(),
+                value,
+            },
+        )
+    }
+
     fn make_transfer(
         sandbox: &mut Sandbox<MinimalRuntime>,
         dest: AccountId32,
@@ -129,4 +217,39 @@ mod tests {
         make_transfer(&mut sandbox, RECIPIENT, 1).expect("Failed to make transfer");
         assert!(!sandbox.events().is_empty());
     }
+
+    #[test]
+    fn building_block_with_extrinsics_works() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().expect("Failed to create sandbox");
+        const RECIPIENT: AccountId32 = AccountId32::new([4u8; 32]);
+        let actor = MinimalRuntime::default_actor();
+
+        let results = sandbox
+            .build_block_with(vec![
+                (transfer_call(RECIPIENT, 100), Some(actor.clone())),
+                (transfer_call(RECIPIENT, u128::MAX), Some(actor)),
+            ])
+            .expect("Failed to build block");
+
+        assert_eq!(sandbox.block_number(), 2);
+        assert!(matches!(results[0], Ok(Ok(_))));
+        assert!(matches!(results[1], Ok(Err(_))));
+        assert_eq!(sandbox.free_balance(&RECIPIENT), 100);
+
+        let events = sandbox.events();
+        assert!(events
+            .iter()
+            .any(|record| record.phase == Phase::ApplyExtrinsic(0)
+                && matches!(
+                    record.event,
+                    RuntimeEvent::System(frame_system::Event::ExtrinsicSuccess { .. })
+                )));
+        assert!(events
+            .iter()
+            .any(|record| record.phase == Phase::ApplyExtrinsic(1)
+                && matches!(
+                    record.event,
+                    RuntimeEvent::System(frame_system::Event::ExtrinsicFailed { .. })
+                )));
+    }
 }
@@ -1,10 +1,119 @@
 //! timestamp API for the sandbox.
 
-use crate::{Sandbox, SandboxConfig};
+use std::time::{Duration, SystemTime};
+
+use frame_support::storage::unhashed;
+use frame_system::pallet_prelude::BlockNumberFor;
+use parity_scale_codec::{Decode, Encode};
+
+use crate::{DrinkResult, Sandbox, SandboxConfig};
 
 /// Generic Time type.
 type MomentOf<R> = <R as pallet_timestamp::Config>::Moment;
 
+/// Storage key under which the time source of the sandbox is kept.
+const TIME_SOURCE_KEY: &[u8] = b":drink:time_source";
+/// Storage key under which a pending time warp (to be applied to the next block) is kept.
+const TIME_WARP_KEY: &[u8] = b":drink:time_warp";
+/// Storage key under which the exact timestamp of the next block (if requested) is kept.
+const NEXT_TIMESTAMP_KEY: &[u8] = b":drink:next_timestamp";
+/// Storage key under which the sum of all time warps applied in the `TimeSource::SystemTime` mode
+/// is kept.
+const SYSTEM_TIME_OFFSET_KEY: &[u8] = b":drink:system_time_offset";
+
+/// Source of block timestamps in the sandbox.
+///
+/// The time source is consulted by `SandboxConfig::initialize_block` (as implemented by
+/// `impl_sandbox_config!`) whenever a new block is built.
+#[derive(Encode, Decode, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeSource {
+    /// Every block gets the current system time (in milliseconds since the UNIX epoch, like
+    /// `pallet_timestamp` expects).
+    #[default]
+    SystemTime,
+    /// The first block gets `genesis` and every next block is `increment` later than the previous
+    /// one.
+    Linear {
+        /// Timestamp of the block, in which the time source is set.
+        genesis: u64,
+        /// Difference between the timestamps of two consecutive blocks.
+        increment: u64,
+    },
+    /// Every block gets the same timestamp as the previous one.
+    Frozen,
+}
+
+impl TimeSource {
+    /// Returns the time source currently configured in the externalities.
+    pub fn current() -> Self {
+        unhashed::get(TIME_SOURCE_KEY).unwrap_or_default()
+    }
+
+    /// Computes the timestamp of a new block, given the timestamp of the previous one.
+    pub fn next_timestamp(&self, previous: u64) -> u64 {
+        match self {
+            Self::SystemTime => SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .expect("Time went backwards")
+                .as_millis()
+                .try_into()
+                .unwrap_or(u64::MAX),
+            Self::Linear { increment, .. } => previous.saturating_add(*increment),
+            Self::Frozen => previous,
+        }
+    }
+}
+
+/// Computes the timestamp of a new block according to the configured `TimeSource`, applying (and
+/// consuming) a pending time warp, if any. A timestamp requested with `set_next_block_timestamp`
+/// takes precedence over both.
+///
+/// Apart from an explicitly requested timestamp, the result is never lower than `previous`.
+///
+/// Meant to be called from `SandboxConfig::initialize_block`. Must be called within the
+/// externalities.
+pub fn next_block_timestamp(previous: u64) -> u64 {
+    let warp = unhashed::take::<u64>(TIME_WARP_KEY).unwrap_or_default();
+    if let Some(timestamp) = unhashed::take::<u64>(NEXT_TIMESTAMP_KEY) {
+        return timestamp;
+    }
+
+    let source = TimeSource::current();
+    let offset = match source {
+        // The system time doesn't depend on `previous`, so the warps have to be remembered and
+        // applied to every next block.
+        TimeSource::SystemTime => {
+            let offset = unhashed::get::<u64>(SYSTEM_TIME_OFFSET_KEY)
+                .unwrap_or_default()
+                .saturating_add(warp);
+            unhashed::put(SYSTEM_TIME_OFFSET_KEY, &offset);
+            offset
+        }
+        TimeSource::Linear { .. } | TimeSource::Frozen => warp,
+    };
+    source
+        .next_timestamp(previous)
+        .saturating_add(offset)
+        .max(previous)
+}
+
+/// Makes the next block get exactly `timestamp` (in milliseconds), regardless of the time source.
+pub(crate) fn set_next_block_timestamp(timestamp: u64) {
+    unhashed::put(NEXT_TIMESTAMP_KEY, &timestamp);
+}
+
+/// Makes the timestamp of the next block account for `blocks` skipped blocks. Only relevant for
+/// `TimeSource::Linear`.
+pub(crate) fn account_for_skipped_blocks(blocks: u64) {
+    if let TimeSource::Linear { increment, .. } = TimeSource::current() {
+        let pending = unhashed::get::<u64>(TIME_WARP_KEY).unwrap_or_default();
+        unhashed::put(
+            TIME_WARP_KEY,
+            &pending.saturating_add(increment.saturating_mul(blocks)),
+        );
+    }
+}
+
 impl<Config: SandboxConfig> Sandbox<Config>
 where
     Config::Runtime: pallet_timestamp::Config,
@@ -22,10 +131,54 @@ where
     pub fn set_timestamp(&mut self, timestamp: MomentOf<Config::Runtime>) {
         self.execute_with(|| pallet_timestamp::Pallet::<Config::Runtime>::set_timestamp(timestamp))
     }
+
+    /// Set the source of timestamps for the next blocks.
+    ///
+    /// Setting `TimeSource::Linear` also resets the timestamp of the current block to `genesis`.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The new time source.
+    pub fn set_time_source(&mut self, source: TimeSource)
+    where
+        MomentOf<Config::Runtime>: From<u64>,
+    {
+        self.execute_with(|| unhashed::put(TIME_SOURCE_KEY, &source));
+        if let TimeSource::Linear { genesis, .. } = source {
+            self.set_timestamp(genesis.into());
+        }
+    }
+}
+
+impl<Config: SandboxConfig> Sandbox<Config> {
+    /// Return the currently configured source of block timestamps.
+    pub fn time_source(&mut self) -> TimeSource {
+        self.execute_with(TimeSource::current)
+    }
+
+    /// Build a new block, whose timestamp is additionally moved forward by `duration` (interpreted
+    /// in milliseconds). The shift persists for all the next blocks, and consecutive warps add up.
+    ///
+    /// Returns the new height.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - How much time should pass.
+    pub fn warp_time(
+        &mut self,
+        duration: Duration,
+    ) -> DrinkResult<BlockNumberFor<Config::Runtime>> {
+        let millis = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+        self.execute_with(|| unhashed::put(TIME_WARP_KEY, &millis));
+        self.build_block()
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
+    use super::TimeSource;
     use crate::{runtime::MinimalRuntime, Sandbox};
 
     #[test]
@@ -39,4 +192,88 @@ mod tests {
             sandbox.build_block().expect("Failed to build block");
         }
     }
+
+    #[test]
+    fn linear_time_source_is_deterministic() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().expect("Failed to create sandbox");
+        sandbox.set_time_source(TimeSource::Linear {
+            genesis: 1_000,
+            increment: 6_000,
+        });
+        assert_eq!(sandbox.get_timestamp(), 1_000);
+
+        sandbox.build_blocks(2).expect("Failed to build blocks");
+        assert_eq!(sandbox.get_timestamp(), 13_000);
+
+        // Manually set timestamp is respected by the next blocks.
+        sandbox.set_timestamp(100_000);
+        sandbox.build_block().expect("Failed to build block");
+        assert_eq!(sandbox.get_timestamp(), 106_000);
+    }
+
+    #[test]
+    fn frozen_time_source_keeps_timestamp() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().expect("Failed to create sandbox");
+        sandbox.set_time_source(TimeSource::Frozen);
+        sandbox.set_timestamp(42);
+
+        sandbox.build_blocks(3).expect("Failed to build blocks");
+        assert_eq!(sandbox.get_timestamp(), 42);
+    }
+
+    #[test]
+    fn system_time_is_in_milliseconds() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().expect("Failed to create sandbox");
+        let before = TimeSource::SystemTime.next_timestamp(0);
+
+        sandbox
+            .warp_time(Duration::from_secs(3600))
+            .expect("Failed to warp time");
+        let warped = sandbox.get_timestamp();
+        assert!(warped >= before + 3_600_000);
+        // A few seconds of slack for the test execution itself.
+        assert!(warped < before + 3_610_000);
+    }
+
+    #[test]
+    fn system_time_warps_accumulate() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().expect("Failed to create sandbox");
+        let before = TimeSource::SystemTime.next_timestamp(0);
+
+        sandbox
+            .warp_time(Duration::from_secs(3600))
+            .expect("Failed to warp time");
+        let warped = sandbox.get_timestamp();
+
+        sandbox.build_block().expect("Failed to build block");
+        let next = sandbox.get_timestamp();
+        assert!(next >= warped);
+
+        sandbox
+            .warp_time(Duration::from_secs(3600))
+            .expect("Failed to warp time");
+        sandbox.build_block().expect("Failed to build block");
+        let last = sandbox.get_timestamp();
+        assert!(last >= next);
+        assert!(last >= before + 7_200_000);
+    }
+
+    #[test]
+    fn warping_time_works() {
+        let mut sandbox = Sandbox::<MinimalRuntime>::new().expect("Failed to create sandbox");
+        sandbox.set_time_source(TimeSource::Linear {
+            genesis: 0,
+            increment: 1_000,
+        });
+
+        let height = sandbox.block_number();
+        let new_height = sandbox
+            .warp_time(Duration::from_secs(3600))
+            .expect("Failed to warp time");
+        assert_eq!(new_height, height + 1);
+        assert_eq!(sandbox.get_timestamp(), 3_601_000);
+
+        sandbox.build_block().expect("Failed to build block");
+        assert_eq!(sandbox.get_timestamp(), 3_602_000);
+    }
 }
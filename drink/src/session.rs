@@ -5,37 +5,57 @@ use std::{
     mem,
     rc::Rc,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 pub use contract_transcode;
 use contract_transcode::ContractMessageTranscoder;
-use frame_support::{traits::fungible::Inspect, weights::Weight};
-use pallet_contracts::Determinism;
-use parity_scale_codec::Decode;
-pub use record::{EventBatch, Record};
+use frame_support::{
+    sp_runtime::AccountId32,
+    traits::fungible::{Inspect, Mutate},
+    weights::Weight,
+};
+use frame_system::pallet_prelude::BlockNumberFor;
+use pallet_contracts::{Code, Determinism};
+use parity_scale_codec::{Decode, Encode, HasCompact};
+pub use record::{DepositLedger, DepositMovement, DispatchedCall, EventBatch, Record, Termination};
+use scale_info::TypeInfo;
 
 use crate::{
+    balance_api::RuntimeHoldReasonOf,
     mock::MockRegistry,
     runtime::{
+        call_filter::{encoded_call_filter, CallFilterExt, CallFilterState, RecordingCallFilter},
+        chain_extension::{
+            ChainExtensionExt, ChainExtensionHandler, ChainExtensionHandlers, HandlersExtension,
+        },
         pallet_contracts_debugging::{InterceptingExt, TracingExt},
         AccountIdFor, HashFor,
     },
     sandbox::SandboxConfig,
-    MockingExtension, Sandbox, DEFAULT_GAS_LIMIT,
+    MockingExtension, RuntimeCall, Sandbox, DEFAULT_GAS_LIMIT,
 };
 
+pub mod accounts;
 pub mod error;
 pub mod mocking_api;
 mod record;
+pub mod storage;
 mod transcoding;
+pub mod transcript;
 
 use error::SessionError;
 
-use self::mocking_api::MockingApi;
-use crate::{
-    bundle::ContractBundle, errors::MessageResult, runtime::MinimalRuntime,
-    session::transcoding::TranscoderRegistry,
+use self::{
+    accounts::{Account, AccountRegistry, KeyScheme},
+    mocking_api::MockingApi,
+    storage::StorageCell,
+    transcript::{
+        DivergenceKind, IdentifierMap, InteractionContext, ReplayReport, ReplaySources, Transcript,
+        TranscriptEntry,
+    },
 };
+use crate::{bundle::ContractBundle, errors::MessageResult, runtime::MinimalRuntime};
 
 type BalanceOf<R> =
     <<R as pallet_contracts::Config>::Currency as Inspect<AccountIdFor<R>>>::Balance;
@@ -141,9 +161,11 @@ where
     gas_limit: Weight,
     determinism: Determinism,
 
-    transcoders: TranscoderRegistry<AccountIdFor<Config::Runtime>>,
     record: Record<Config::Runtime>,
     mocks: Arc<Mutex<MockRegistry<AccountIdFor<Config::Runtime>>>>,
+    chain_extension_handlers: Arc<Mutex<ChainExtensionHandlers>>,
+    call_filter: Arc<Mutex<CallFilterState>>,
+    accounts: AccountRegistry,
 }
 
 impl<Config: SandboxConfig> Session<Config>
@@ -157,15 +179,25 @@ where
         sandbox.register_extension(InterceptingExt(Box::new(MockingExtension {
             mock_registry: Arc::clone(&mocks),
         })));
+        let chain_extension_handlers = Arc::new(Mutex::new(ChainExtensionHandlers::default()));
+        sandbox.register_extension(ChainExtensionExt(Box::new(HandlersExtension {
+            handlers: Arc::clone(&chain_extension_handlers),
+        })));
+        let call_filter = Arc::new(Mutex::new(CallFilterState::default()));
+        sandbox.register_extension(CallFilterExt(Box::new(RecordingCallFilter {
+            state: Arc::clone(&call_filter),
+        })));
 
         Ok(Self {
             sandbox,
             mocks,
+            chain_extension_handlers,
+            call_filter,
             actor: Config::default_actor(),
             gas_limit: DEFAULT_GAS_LIMIT,
             determinism: Determinism::Enforced,
-            transcoders: TranscoderRegistry::new(),
             record: Default::default(),
+            accounts: Default::default(),
         })
     }
 
@@ -231,7 +263,8 @@ where
         contract_address: AccountIdFor<Config::Runtime>,
         transcoder: &Rc<ContractMessageTranscoder>,
     ) {
-        self.transcoders.register(contract_address, transcoder);
+        self.record
+            .register_transcoder(contract_address, transcoder);
     }
 
     /// The underlying `Sandbox` instance.
@@ -272,12 +305,30 @@ where
     }
     fn record_events<T>(&mut self, recording: impl FnOnce(&mut Self) -> T) -> T {
         let start = self.sandbox.events().len();
+        // Drop the calls dispatched outside of the session interactions (e.g. directly through
+        // the sandbox).
+        self.take_runtime_calls();
         let result = recording(self);
         let events = self.sandbox.events()[start..].to_vec();
-        self.record.push_event_batches(events);
+        let runtime_calls = self.take_runtime_calls();
+        self.record.push_event_batches(events, runtime_calls);
         result
     }
 
+    fn take_runtime_calls(&mut self) -> Vec<DispatchedCall<Config::Runtime>> {
+        let dispatched = mem::take(
+            &mut self
+                .call_filter
+                .lock()
+                .expect("Should be able to acquire call filter state")
+                .dispatched,
+        );
+        dispatched
+            .into_iter()
+            .map(|(call, allowed)| DispatchedCall::new(call, allowed))
+            .collect()
+    }
+
     /// Deploys a contract with a given constructor, arguments, salt and endowment. In case of
     /// success, returns the address of the deployed contract.
     pub fn deploy<S: AsRef<str> + Debug>(
@@ -288,21 +339,134 @@ where
         salt: Vec<u8>,
         endowment: Option<BalanceOf<Config::Runtime>>,
         transcoder: &Rc<ContractMessageTranscoder>,
+    ) -> Result<AccountIdFor<Config::Runtime>, SessionError> {
+        self.deploy_internal(
+            Code::Upload(contract_bytes),
+            constructor,
+            args,
+            salt,
+            endowment,
+            transcoder,
+        )
+    }
+
+    /// Instantiates a new contract from an already uploaded code (e.g. with `upload_bundle`), with a
+    /// given constructor, arguments, salt and endowment. In case of success, returns the address of
+    /// the new contract.
+    ///
+    /// Unlike `deploy`, the code is not uploaded again, so the recorded gas consumption and storage
+    /// deposit concern only the instantiation. Messages are encoded with the transcoder of the
+    /// bundle that the code has been uploaded or deployed from.
+    pub fn instantiate<S: AsRef<str> + Debug>(
+        &mut self,
+        code_hash: HashFor<Config::Runtime>,
+        constructor: &str,
+        args: &[S],
+        salt: Vec<u8>,
+        endowment: Option<BalanceOf<Config::Runtime>>,
+    ) -> Result<AccountIdFor<Config::Runtime>, SessionError> {
+        let transcoder = self
+            .record
+            .code_transcoder(&code_hash)
+            .ok_or(SessionError::NoTranscoder)?;
+        self.deploy_internal(
+            Code::Existing(code_hash),
+            constructor,
+            args,
+            salt,
+            endowment,
+            &transcoder,
+        )
+    }
+
+    /// Similar to `instantiate`, but in case of success returns `self`.
+    pub fn instantiate_and<S: AsRef<str> + Debug>(
+        mut self,
+        code_hash: HashFor<Config::Runtime>,
+        constructor: &str,
+        args: &[S],
+        salt: Vec<u8>,
+        endowment: Option<BalanceOf<Config::Runtime>>,
+    ) -> Result<Self, SessionError> {
+        self.instantiate(code_hash, constructor, args, salt, endowment)
+            .map(|_| self)
+    }
+
+    /// Returns the address of a contract that would be instantiated by `deployer` from `code_hash`
+    /// with the given constructor input (encoded, including the selector) and salt.
+    ///
+    /// The address is computed with the `AddressGenerator` of the runtime, so it can be used before
+    /// the contract exists, e.g. as a constructor argument of another contract.
+    pub fn predict_address(
+        &mut self,
+        deployer: &AccountIdFor<Config::Runtime>,
+        code_hash: &HashFor<Config::Runtime>,
+        input: &[u8],
+        salt: &[u8],
+    ) -> AccountIdFor<Config::Runtime> {
+        self.sandbox
+            .predict_contract_address(deployer, code_hash, input, salt)
+    }
+
+    /// Similar to `predict_address`, but for an instantiation by the current actor, with the
+    /// constructor input encoded like in `instantiate`.
+    pub fn predict_instantiation_address<S: AsRef<str> + Debug>(
+        &mut self,
+        code_hash: &HashFor<Config::Runtime>,
+        constructor: &str,
+        args: &[S],
+        salt: &[u8],
+    ) -> Result<AccountIdFor<Config::Runtime>, SessionError> {
+        let input = self
+            .record
+            .code_transcoder(code_hash)
+            .ok_or(SessionError::NoTranscoder)?
+            .encode(constructor, self.accounts.resolve_args(args))
+            .map_err(|err| SessionError::Encoding(err.to_string()))?;
+        let deployer = self.actor.clone();
+        Ok(self.predict_address(&deployer, code_hash, &input, salt))
+    }
+
+    fn deploy_internal<S: AsRef<str> + Debug>(
+        &mut self,
+        code: Code<HashFor<Config::Runtime>>,
+        constructor: &str,
+        args: &[S],
+        salt: Vec<u8>,
+        endowment: Option<BalanceOf<Config::Runtime>>,
+        transcoder: &Rc<ContractMessageTranscoder>,
     ) -> Result<AccountIdFor<Config::Runtime>, SessionError> {
         let data = transcoder
-            .encode(constructor, args)
+            .encode(constructor, self.accounts.resolve_args(args))
             .map_err(|err| SessionError::Encoding(err.to_string()))?;
+        let (code_hash, uploaded) = match &code {
+            Code::Upload(contract_bytes) => (
+                transcript::code_hash::<Config::Runtime>(contract_bytes),
+                true,
+            ),
+            Code::Existing(code_hash) => (*code_hash, false),
+        };
+        self.record.register_code_transcoder(code_hash, transcoder);
 
-        let result = self.record_events(|session| {
-            session.sandbox.deploy_contract(
+        let result = self.record_events(|session| match code {
+            Code::Upload(contract_bytes) => session.sandbox.deploy_contract(
                 contract_bytes,
                 endowment.unwrap_or_default(),
                 data,
-                salt,
+                salt.clone(),
                 session.actor.clone(),
                 session.gas_limit,
                 None,
-            )
+            ),
+            Code::Existing(code_hash) => session.sandbox.instantiate_contract(
+                code_hash.encode(),
+                endowment.unwrap_or_default(),
+                data,
+                salt.clone(),
+                session.actor.clone(),
+                session.gas_limit,
+                None,
+            ),
         });
 
         let ret = match &result.result {
@@ -312,14 +476,26 @@ where
             Ok(exec_result) => {
                 let address = exec_result.account_id.clone();
                 self.record.push_deploy_return(address.clone());
-                self.transcoders.register(address.clone(), transcoder);
+                self.record.register_transcoder(address.clone(), transcoder);
 
                 Ok(address)
             }
             Err(err) => Err(SessionError::DeploymentFailed(*err)),
         };
 
+        self.record.push_transcript_entry(TranscriptEntry::Deploy {
+            context: self.interaction_context(),
+            code_hash,
+            uploaded,
+            constructor: constructor.to_string(),
+            args: args.iter().map(|arg| arg.as_ref().to_string()).collect(),
+            salt,
+            endowment,
+            outcome: transcript::Outcome::of_deploy::<Config::Runtime>(&result),
+            events: self.last_batch_encoded(),
+        });
         self.record.push_deploy_result(result);
+        self.forget_terminated_contracts();
         ret
     }
 
@@ -369,6 +545,7 @@ where
         &mut self,
         contract_bytes: Vec<u8>,
     ) -> Result<HashFor<Config::Runtime>, SessionError> {
+        let code_hash = transcript::code_hash::<Config::Runtime>(&contract_bytes);
         let result = self.sandbox.upload_contract(
             contract_bytes,
             self.actor.clone(),
@@ -376,6 +553,16 @@ where
             self.determinism,
         );
 
+        let outcome = match &result {
+            Ok(upload_result) => transcript::Outcome::Success(upload_result.code_hash.encode()),
+            Err(err) => transcript::Outcome::Failed(*err),
+        };
+        self.record.push_transcript_entry(TranscriptEntry::Upload {
+            actor: self.actor.clone(),
+            code_hash,
+            outcome,
+        });
+
         result
             .map(|upload_result| upload_result.code_hash)
             .map_err(SessionError::UploadFailed)
@@ -384,8 +571,11 @@ where
     /// Similar to `upload_and` but takes the contract bundle as the first argument.
     ///
     /// You can obtain it using `ContractBundle::load("some/path/your.contract")` or `local_contract_file!()`
-    pub fn upload_bundle_and(self, contract_file: ContractBundle) -> Result<Self, SessionError> {
-        self.upload_and(contract_file.wasm)
+    pub fn upload_bundle_and(
+        mut self,
+        contract_file: ContractBundle,
+    ) -> Result<Self, SessionError> {
+        self.upload_bundle(contract_file).map(|_| self)
     }
 
     /// Similar to `upload` but takes the contract bundle as the first argument.
@@ -395,7 +585,142 @@ where
         &mut self,
         contract_file: ContractBundle,
     ) -> Result<HashFor<Config::Runtime>, SessionError> {
-        self.upload(contract_file.wasm)
+        let code_hash = self.upload(contract_file.wasm)?;
+        self.record
+            .register_code_transcoder(code_hash, &contract_file.transcoder);
+        Ok(code_hash)
+    }
+
+    /// Removes an uploaded code, which is not used by any contract. The code must have been uploaded
+    /// by the current actor, who gets the code deposit refunded.
+    pub fn remove_code(&mut self, code_hash: HashFor<Config::Runtime>) -> Result<(), SessionError>
+    where
+        <BalanceOf<Config::Runtime> as HasCompact>::Type: Clone + Eq + Debug + TypeInfo + Encode,
+    {
+        let result = self.sandbox.remove_code(code_hash, self.actor.clone());
+        self.record
+            .push_transcript_entry(TranscriptEntry::RemoveCode {
+                actor: self.actor.clone(),
+                code_hash,
+                outcome: match &result {
+                    Ok(()) => transcript::Outcome::Success(vec![]),
+                    Err(err) => transcript::Outcome::Failed(*err),
+                },
+            });
+        result.map_err(SessionError::RemoveCodeFailed)
+    }
+
+    /// Upgrades the contract at `address` to the code from `new_bundle`.
+    ///
+    /// First, the existing storage is checked to decode with the new storage layout (see
+    /// `decoded_storage`). Replacing the code doesn't touch the storage, so if the check fails, the
+    /// upgrade is abandoned with `SessionError::IncompatibleStorage` and the contract is left
+    /// unchanged. Otherwise, the new code is uploaded and set for the contract like
+    /// `pallet_contracts::Call::set_code` dispatched by root, and the transcoder of the new bundle
+    /// is registered for the contract. Returns the hash of the new code.
+    pub fn upgrade(
+        &mut self,
+        address: AccountIdFor<Config::Runtime>,
+        new_bundle: ContractBundle,
+    ) -> Result<HashFor<Config::Runtime>, SessionError>
+    where
+        <BalanceOf<Config::Runtime> as HasCompact>::Type: Clone + Eq + Debug + TypeInfo + Encode,
+    {
+        self.check_storage_compat(&address, &new_bundle.transcoder)?;
+        let code_hash = self.upload_bundle(new_bundle.clone())?;
+        self.set_code(address.clone(), code_hash)?;
+        self.record
+            .register_transcoder(address, &new_bundle.transcoder);
+        Ok(code_hash)
+    }
+
+    /// Similar to `upgrade`, but the contract replaces its code by itself: after uploading the new
+    /// code, `message` is called with the arguments built by `args` from the new code hash.
+    ///
+    /// Since the message may migrate the storage, the storage is checked only after the call. If
+    /// the check fails, the new code is already in place, but the old transcoder is kept.
+    ///
+    /// ```rust, ignore
+    /// session.upgrade_with_message(address, new_bundle, "set_code", |hash| vec![format!("{hash:?}")])?;
+    /// ```
+    pub fn upgrade_with_message<S: AsRef<str> + Debug>(
+        &mut self,
+        address: AccountIdFor<Config::Runtime>,
+        new_bundle: ContractBundle,
+        message: &str,
+        args: impl FnOnce(HashFor<Config::Runtime>) -> Vec<S>,
+    ) -> Result<HashFor<Config::Runtime>, SessionError> {
+        let code_hash = self.upload_bundle(new_bundle.clone())?;
+        self.call_with_address::<_, ()>(address.clone(), message, &args(code_hash), None)?
+            .map_err(SessionError::UpgradeMessageFailed)?;
+        self.check_storage_compat(&address, &new_bundle.transcoder)?;
+        self.record
+            .register_transcoder(address, &new_bundle.transcoder);
+        Ok(code_hash)
+    }
+
+    /// Decodes the storage of the contract at `address` with the storage layout from its
+    /// transcoder.
+    ///
+    /// All the root cells (the contract struct and `Lazy` fields) are decoded. Entries of
+    /// `Mapping`s are not.
+    pub fn decoded_storage(
+        &mut self,
+        address: &AccountIdFor<Config::Runtime>,
+    ) -> Result<Vec<StorageCell>, SessionError> {
+        let transcoder = self
+            .record
+            .transcoder(address)
+            .ok_or(SessionError::NoTranscoder)?;
+        self.decode_storage_with(address, &transcoder)
+    }
+
+    fn decode_storage_with(
+        &mut self,
+        address: &AccountIdFor<Config::Runtime>,
+        transcoder: &ContractMessageTranscoder,
+    ) -> Result<Vec<StorageCell>, SessionError> {
+        storage::decode_storage(transcoder, |key| {
+            self.sandbox
+                .contract_storage(address.clone(), key)
+                .map_err(|err| format!("{err:?}"))
+        })
+        .map_err(SessionError::Decoding)
+    }
+
+    fn set_code(
+        &mut self,
+        address: AccountIdFor<Config::Runtime>,
+        code_hash: HashFor<Config::Runtime>,
+    ) -> Result<(), SessionError>
+    where
+        <BalanceOf<Config::Runtime> as HasCompact>::Type: Clone + Eq + Debug + TypeInfo + Encode,
+    {
+        let result = self.sandbox.set_contract_code(address.clone(), code_hash);
+        self.record.push_transcript_entry(TranscriptEntry::SetCode {
+            address,
+            code_hash,
+            outcome: match &result {
+                Ok(()) => transcript::Outcome::Success(vec![]),
+                Err(err) => transcript::Outcome::Failed(*err),
+            },
+        });
+        result.map_err(SessionError::UpgradeFailed)
+    }
+
+    /// Checks that the storage of the contract at `address` decodes with the storage layout from
+    /// `transcoder`.
+    fn check_storage_compat(
+        &mut self,
+        address: &AccountIdFor<Config::Runtime>,
+        transcoder: &ContractMessageTranscoder,
+    ) -> Result<(), SessionError> {
+        self.decode_storage_with(address, transcoder)
+            .map(|_| ())
+            .map_err(|err| match err {
+                SessionError::Decoding(reason) => SessionError::IncompatibleStorage(reason),
+                err => err,
+            })
     }
 
     /// Calls a contract with a given address. In case of a successful call, returns `self`.
@@ -465,16 +790,15 @@ where
         };
 
         let data = self
-            .transcoders
-            .get(&address)
-            .as_ref()
+            .record
+            .transcoder(&address)
             .ok_or(SessionError::NoTranscoder)?
-            .encode(message, args)
+            .encode(message, self.accounts.resolve_args(args))
             .map_err(|err| SessionError::Encoding(err.to_string()))?;
 
         let result = self.record_events(|session| {
             session.sandbox.call_contract(
-                address,
+                address.clone(),
                 endowment.unwrap_or_default(),
                 data,
                 session.actor.clone(),
@@ -493,12 +817,426 @@ where
             Err(err) => Err(SessionError::CallFailed(*err)),
         };
 
+        self.record.push_transcript_entry(TranscriptEntry::Call {
+            context: self.interaction_context(),
+            address,
+            message: message.to_string(),
+            args: args.iter().map(|arg| arg.as_ref().to_string()).collect(),
+            endowment,
+            outcome: transcript::Outcome::of_call::<Config::Runtime>(&result),
+            events: self.last_batch_encoded(),
+        });
         self.record.push_call_result(result);
+        self.forget_terminated_contracts();
         ret
     }
 
+    /// Returns the storage deposit currently held for the contract at `address`.
+    ///
+    /// Together with `Record::storage_deposits`, it allows checking that the deposit charged so
+    /// far has actually been refunded by storage cleanup.
+    pub fn held_storage_deposit(
+        &mut self,
+        address: &AccountIdFor<Config::Runtime>,
+    ) -> crate::BalanceOf<Config::Runtime>
+    where
+        Config::Runtime: pallet_balances::Config,
+        RuntimeHoldReasonOf<Config::Runtime>: From<pallet_contracts::HoldReason>,
+    {
+        self.sandbox.storage_deposit_on_hold(address)
+    }
+
+    /// Registers `handler` for the chain extension function `(extension_id, func_id)` and returns
+    /// updated `self`.
+    ///
+    /// The handlers are used by runtimes with `ChainExtensionRegistry` as their chain extension.
+    pub fn with_chain_extension_handler(
+        mut self,
+        extension_id: u16,
+        func_id: u16,
+        handler: ChainExtensionHandler,
+    ) -> Self {
+        self.set_chain_extension_handler(extension_id, func_id, handler);
+        self
+    }
+
+    /// Registers `handler` for the chain extension function `(extension_id, func_id)` and returns
+    /// the previous handler, if any.
+    ///
+    /// The handlers are used by runtimes with `ChainExtensionRegistry` as their chain extension.
+    pub fn set_chain_extension_handler(
+        &mut self,
+        extension_id: u16,
+        func_id: u16,
+        handler: ChainExtensionHandler,
+    ) -> Option<ChainExtensionHandler> {
+        self.chain_extension_handlers
+            .lock()
+            .expect("Should be able to acquire chain extension handlers")
+            .register(extension_id, func_id, handler)
+    }
+
+    /// Removes the handler of the chain extension function `(extension_id, func_id)` and returns
+    /// it, if any. Further calls to the function will trap the contract.
+    pub fn remove_chain_extension_handler(
+        &mut self,
+        extension_id: u16,
+        func_id: u16,
+    ) -> Option<ChainExtensionHandler> {
+        self.chain_extension_handlers
+            .lock()
+            .expect("Should be able to acquire chain extension handlers")
+            .remove(extension_id, func_id)
+    }
+
+    /// Sets the filter deciding which runtime calls contracts can dispatch (with `call_runtime`)
+    /// and returns updated `self`.
+    ///
+    /// The filter is used by runtimes with `SandboxCallFilter` as the contracts call filter. Since
+    /// `call_runtime` is an unstable host function, the runtime must also enable the unstable
+    /// interface (see `create_minimal_runtime!`).
+    pub fn with_runtime_call_filter(
+        mut self,
+        filter: impl Fn(&RuntimeCall<Config::Runtime>) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.set_runtime_call_filter(filter);
+        self
+    }
+
+    /// Sets the filter deciding which runtime calls contracts can dispatch (with `call_runtime`).
+    ///
+    /// By default, no call is allowed. Use `|_| true` to allow all the calls. The filter is also a
+    /// convenient place to observe the calls, but all of them (including the forbidden ones) are
+    /// recorded anyway, see `EventBatch::runtime_calls`.
+    ///
+    /// The filter cannot stub the result of a call: an allowed call is dispatched to the runtime
+    /// pallets, and a forbidden one fails with `CallRuntimeFailed`.
+    pub fn set_runtime_call_filter(
+        &mut self,
+        filter: impl Fn(&RuntimeCall<Config::Runtime>) -> bool + Send + Sync + 'static,
+    ) {
+        self.call_filter
+            .lock()
+            .expect("Should be able to acquire call filter state")
+            .filter = Some(encoded_call_filter(filter));
+    }
+
+    /// Removes the runtime call filter, so that no runtime call can be dispatched by contracts.
+    pub fn clear_runtime_call_filter(&mut self) {
+        self.call_filter
+            .lock()
+            .expect("Should be able to acquire call filter state")
+            .filter = None;
+    }
+
+    /// Replaces the chain extension runtime extension.
+    ///
+    /// From now on, the calls of `ChainExtensionRegistry` are forwarded to `ext` instead of the
+    /// handlers registered on the session.
+    pub fn set_chain_extension(&mut self, ext: ChainExtensionExt) {
+        self.sandbox.register_extension(ext);
+    }
+
     /// Set the tracing extension
     pub fn set_tracing_extension(&mut self, d: TracingExt) {
         self.sandbox.register_extension(d);
     }
+
+    /// Builds `n` empty blocks and returns the new height.
+    ///
+    /// Unlike building blocks directly on the sandbox, this is noted in the session transcript.
+    pub fn build_blocks(
+        &mut self,
+        n: u32,
+    ) -> Result<BlockNumberFor<Config::Runtime>, SessionError> {
+        let height = self.sandbox.build_blocks(n)?;
+        self.record
+            .push_transcript_entry(TranscriptEntry::BuildBlocks { count: n });
+        Ok(height)
+    }
+
+    /// Builds a new block with the clock moved forward by `duration` and returns the new height.
+    ///
+    /// See `Sandbox::warp_time` for details.
+    pub fn warp_time(
+        &mut self,
+        duration: Duration,
+    ) -> Result<BlockNumberFor<Config::Runtime>, SessionError> {
+        let height = self.sandbox.warp_time(duration)?;
+        self.record
+            .push_transcript_entry(TranscriptEntry::WarpTime {
+                millis: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+            });
+        Ok(height)
+    }
+
+    /// Mints `amount` tokens to `recipient` and returns the minted amount.
+    ///
+    /// Unlike minting directly on the sandbox, this is noted in the session transcript.
+    pub fn mint_into(
+        &mut self,
+        recipient: AccountIdFor<Config::Runtime>,
+        amount: BalanceOf<Config::Runtime>,
+    ) -> Result<BalanceOf<Config::Runtime>, SessionError> {
+        let minted = self
+            .sandbox
+            .execute_with(|| {
+                <Config::Runtime as pallet_contracts::Config>::Currency::mint_into(
+                    &recipient, amount,
+                )
+            })
+            .map_err(SessionError::FundingFailed)?;
+        self.record
+            .push_transcript_entry(TranscriptEntry::Mint { recipient, amount });
+        Ok(minted)
+    }
+
+    /// Replays `transcript` in this session and reports all the places where the results differ
+    /// from the recorded ones.
+    ///
+    /// Every interaction is executed with the recorded actor and gas limit, using the contracts and
+    /// mocks from `sources`. If a bundle has been substituted with a different version of the code,
+    /// the new addresses and code hashes are translated back to the recorded ones before comparing
+    /// deployment results and events (see `IdentifierMap`). Identifiers passed as string arguments,
+    /// returned by calls or carried in contract event payloads are not translated.
+    ///
+    /// Arguments are recorded with the account references (`@<name>`) unresolved, so the accounts
+    /// have to be registered in this session (see `account`) before replaying.
+    ///
+    /// The original actor and gas limit are restored afterwards.
+    pub fn replay(
+        &mut self,
+        transcript: &Transcript<Config::Runtime>,
+        mut sources: ReplaySources<Config::Runtime>,
+    ) -> Result<ReplayReport, SessionError>
+    where
+        Config::Runtime: pallet_balances::Config,
+        <BalanceOf<Config::Runtime> as HasCompact>::Type: Clone + Eq + Debug + TypeInfo + Encode,
+    {
+        let (actor, gas_limit) = (self.actor.clone(), self.gas_limit);
+        let mut identifiers = IdentifierMap::<Config::Runtime>::new();
+        let mut report = ReplayReport::default();
+
+        for (index, entry) in transcript.entries().iter().enumerate() {
+            let entries_before = self.record.transcript().entries().len();
+
+            let execution = match entry {
+                TranscriptEntry::Upload {
+                    actor, code_hash, ..
+                } => {
+                    let bundle = sources.bundle(code_hash)?;
+                    identifiers.insert_code_hash(
+                        *code_hash,
+                        transcript::code_hash::<Config::Runtime>(&bundle.wasm),
+                    );
+                    self.actor = actor.clone();
+                    self.upload_bundle(bundle).map(|_| ())
+                }
+                TranscriptEntry::Deploy {
+                    context,
+                    code_hash,
+                    uploaded,
+                    constructor,
+                    args,
+                    salt,
+                    endowment,
+                    outcome,
+                    ..
+                } => {
+                    let bundle = sources.bundle(code_hash)?;
+                    identifiers.insert_code_hash(
+                        *code_hash,
+                        transcript::code_hash::<Config::Runtime>(&bundle.wasm),
+                    );
+                    self.apply_context(context);
+                    let result = match uploaded {
+                        true => {
+                            self.deploy_bundle(bundle, constructor, args, salt.clone(), *endowment)
+                        }
+                        false => {
+                            let code_hash = transcript::code_hash::<Config::Runtime>(&bundle.wasm);
+                            self.record
+                                .register_code_transcoder(code_hash, &bundle.transcoder);
+                            self.instantiate(code_hash, constructor, args, salt.clone(), *endowment)
+                        }
+                    };
+                    if let (transcript::Outcome::Success(recorded), Ok(replayed)) =
+                        (outcome, &result)
+                    {
+                        if let Ok(recorded) = Decode::decode(&mut &recorded[..]) {
+                            identifiers.insert_account(recorded, replayed.clone());
+                        }
+                    }
+                    result.map(|_| ())
+                }
+                TranscriptEntry::Call {
+                    context,
+                    address,
+                    message,
+                    args,
+                    endowment,
+                    ..
+                } => {
+                    let address = identifiers.replayed_account(address);
+                    self.apply_context(context);
+                    self.call_with_address::<_, ()>(address, message, args, *endowment)
+                        .map(|_| ())
+                }
+                TranscriptEntry::BuildBlocks { count } => self.build_blocks(*count).map(|_| ()),
+                TranscriptEntry::WarpTime { millis } => {
+                    self.warp_time(Duration::from_millis(*millis)).map(|_| ())
+                }
+                TranscriptEntry::RemoveCode {
+                    actor, code_hash, ..
+                } => {
+                    let bundle = sources.bundle(code_hash)?;
+                    self.actor = actor.clone();
+                    self.remove_code(transcript::code_hash::<Config::Runtime>(&bundle.wasm))
+                }
+                TranscriptEntry::SetCode {
+                    address, code_hash, ..
+                } => {
+                    let bundle = sources.bundle(code_hash)?;
+                    let address = identifiers.replayed_account(address);
+                    let replayed = transcript::code_hash::<Config::Runtime>(&bundle.wasm);
+                    self.set_code(address.clone(), replayed)
+                        .map(|_| self.record.register_transcoder(address, &bundle.transcoder))
+                }
+                TranscriptEntry::Mint { recipient, amount } => self
+                    .mint_into(identifiers.replayed_account(recipient), *amount)
+                    .map(|_| ()),
+                TranscriptEntry::DeployMock { address } => {
+                    let mock = sources.next_mock()?;
+                    let replayed = self.mocking_api().deploy(mock);
+                    identifiers.insert_account(address.clone(), replayed);
+                    Ok(())
+                }
+            };
+
+            let Some(replayed) = self.record.transcript().entries().get(entries_before) else {
+                let reason = execution
+                    .err()
+                    .map(|err| err.to_string())
+                    .unwrap_or_default();
+                report.push(index, DivergenceKind::NotExecuted(reason));
+                continue;
+            };
+
+            if let (Some((expected, expected_events)), Some((actual, actual_events))) = (
+                entry.observations(),
+                identifiers.normalized_observations(replayed),
+            ) {
+                if expected != actual {
+                    report.push(index, DivergenceKind::Outcome { expected, actual });
+                }
+                if expected_events != actual_events {
+                    report.push(
+                        index,
+                        DivergenceKind::Events {
+                            expected: expected_events,
+                            actual: actual_events,
+                        },
+                    );
+                }
+            }
+        }
+
+        self.actor = actor;
+        self.gas_limit = gas_limit;
+        Ok(report)
+    }
+
+    /// Drops the transcoders and mocks of the contracts terminated during the last interaction (see
+    /// `Record::terminations`). The transcoders are still used for decoding the recorded data.
+    fn forget_terminated_contracts(&mut self) {
+        let terminated = self.record.last_event_batch().terminated_contracts();
+        let mut mocks = self
+            .mocks
+            .lock()
+            .expect("Should be able to acquire lock on registry");
+        for address in terminated {
+            self.record.forget_transcoder(&address);
+            mocks.remove(&address);
+        }
+    }
+
+    fn interaction_context(&self) -> InteractionContext<Config::Runtime> {
+        InteractionContext {
+            actor: self.actor.clone(),
+            gas_limit: self.gas_limit,
+        }
+    }
+
+    fn apply_context(&mut self, context: &InteractionContext<Config::Runtime>) {
+        self.actor = context.actor.clone();
+        self.gas_limit = context.gas_limit;
+    }
+
+    fn last_batch_encoded(&self) -> Vec<Vec<u8>> {
+        transcript::encode_events::<Config::Runtime>(self.record.last_event_batch().all_events())
+    }
+}
+
+impl<Config: SandboxConfig> Session<Config>
+where
+    Config::Runtime: pallet_contracts::Config,
+    AccountIdFor<Config::Runtime>: From<AccountId32>,
+{
+    /// Returns the account named `name`, registering it with an sr25519 keypair on first use.
+    ///
+    /// The keypair is derived deterministically from the name (see `KeyPair::from_name`). The
+    /// account is not funded. Once registered, the name prefixed with `@` can be passed to the
+    /// transcoder in place of the account address, e.g.
+    /// `session.call("transfer", &["@alice", "100"], None)`.
+    pub fn account(&mut self, name: &str) -> Result<AccountIdFor<Config::Runtime>, SessionError> {
+        self.account_with_scheme(name, KeyScheme::Sr25519)
+    }
+
+    /// Similar to `account`, but registers the account with a keypair of `scheme`.
+    ///
+    /// An already registered account is returned as is, regardless of `scheme`.
+    pub fn account_with_scheme(
+        &mut self,
+        name: &str,
+        scheme: KeyScheme,
+    ) -> Result<AccountIdFor<Config::Runtime>, SessionError> {
+        self.accounts
+            .get_or_register(name, scheme)
+            .map(|account| account.account_id().into())
+            .map_err(|err| SessionError::InvalidAccountName(format!("{name}: {err:?}")))
+    }
+
+    /// Registers a new account under a fresh name (`account-<n>`), funds it with `balance` and
+    /// returns its address.
+    ///
+    /// The funding is noted in the session transcript (see `mint_into`).
+    pub fn new_funded_account(
+        &mut self,
+        balance: BalanceOf<Config::Runtime>,
+    ) -> Result<AccountIdFor<Config::Runtime>, SessionError> {
+        let name = self.accounts.fresh_name();
+        let account = self.account(&name)?;
+        self.mint_into(account.clone(), balance)?;
+        Ok(account)
+    }
+
+    /// Signs `payload` with the keypair of the registered account named `name`.
+    ///
+    /// See `KeyPair::sign` for the signature format of each scheme.
+    pub fn sign(&self, name: &str, payload: &[u8]) -> Result<Vec<u8>, SessionError> {
+        self.accounts
+            .get(name)
+            .map(|account| account.keypair().sign(payload))
+            .ok_or_else(|| SessionError::NoAccount(name.to_string()))
+    }
+
+    /// Returns the registered account named `name`, together with its keypair.
+    pub fn named_account(&self, name: &str) -> Option<&Account> {
+        self.accounts.get(name)
+    }
+
+    /// Iterates over all the registered accounts, ordered by name.
+    pub fn accounts(&self) -> impl Iterator<Item = &Account> {
+        self.accounts.iter()
+    }
 }
@@ -0,0 +1,334 @@
+//! Named test accounts with deterministic keypairs.
+
+use std::collections::BTreeMap;
+
+use frame_support::sp_runtime::AccountId32;
+use sp_core::{
+    crypto::{SecretStringError, Ss58Codec},
+    ecdsa, ed25519,
+    hashing::{blake2_256, keccak_256},
+    sr25519, Pair,
+};
+
+/// Prefix marking an argument as a reference to a registered account, like `@alice`.
+pub const ACCOUNT_REF_PREFIX: char = '@';
+
+/// Signature scheme of a named account keypair.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeyScheme {
+    /// Schnorrkel/Ristretto (sr25519) keypair, like the substrate development accounts.
+    #[default]
+    Sr25519,
+    /// Ed25519 keypair.
+    Ed25519,
+    /// Secp256k1 ECDSA keypair, with an Ethereum address.
+    Ecdsa,
+}
+
+/// Keypair of a named account.
+#[derive(Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum KeyPair {
+    /// Sr25519 keypair.
+    Sr25519(sr25519::Pair),
+    /// Ed25519 keypair.
+    Ed25519(ed25519::Pair),
+    /// Secp256k1 ECDSA keypair.
+    Ecdsa(ecdsa::Pair),
+}
+
+impl KeyPair {
+    /// Deterministically derives the keypair for `name`.
+    ///
+    /// The keypair is derived from the development phrase with the hard junction `//<name>`, so
+    /// `KeyPair::from_name("Alice", KeyScheme::Sr25519)` is the well-known `Alice` account.
+    pub fn from_name(name: &str, scheme: KeyScheme) -> Result<Self, SecretStringError> {
+        let uri = format!("//{name}");
+        Ok(match scheme {
+            KeyScheme::Sr25519 => Self::Sr25519(sr25519::Pair::from_string(&uri, None)?),
+            KeyScheme::Ed25519 => Self::Ed25519(ed25519::Pair::from_string(&uri, None)?),
+            KeyScheme::Ecdsa => Self::Ecdsa(ecdsa::Pair::from_string(&uri, None)?),
+        })
+    }
+
+    /// The signature scheme of the keypair.
+    pub fn scheme(&self) -> KeyScheme {
+        match self {
+            Self::Sr25519(_) => KeyScheme::Sr25519,
+            Self::Ed25519(_) => KeyScheme::Ed25519,
+            Self::Ecdsa(_) => KeyScheme::Ecdsa,
+        }
+    }
+
+    /// The account id corresponding to the public key.
+    ///
+    /// For ECDSA keypairs it is the blake2-256 hash of the compressed public key, like in
+    /// substrate's `MultiSigner`.
+    pub fn account_id(&self) -> AccountId32 {
+        match self {
+            Self::Sr25519(pair) => pair.public().into(),
+            Self::Ed25519(pair) => pair.public().into(),
+            Self::Ecdsa(pair) => blake2_256(pair.public().as_ref()).into(),
+        }
+    }
+
+    /// The raw public key: 32 bytes for sr25519 and ed25519, 33 bytes (compressed) for ECDSA.
+    pub fn public_key(&self) -> Vec<u8> {
+        match self {
+            Self::Sr25519(pair) => pair.public().0.to_vec(),
+            Self::Ed25519(pair) => pair.public().0.to_vec(),
+            Self::Ecdsa(pair) => pair.public().0.to_vec(),
+        }
+    }
+
+    /// Signs `payload` and returns the raw signature.
+    ///
+    /// The signature is 64 bytes for sr25519 and ed25519, which is what `sr25519_verify` expects.
+    /// For ECDSA, `payload` is hashed with blake2-256 first and the signature is 65 bytes
+    /// (recoverable), as expected by `ecdsa_recover` together with `blake2_256(payload)`. Use
+    /// `sign_prehashed` to sign a hash computed differently, e.g. with keccak-256.
+    pub fn sign(&self, payload: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sr25519(pair) => pair.sign(payload).0.to_vec(),
+            Self::Ed25519(pair) => pair.sign(payload).0.to_vec(),
+            Self::Ecdsa(pair) => pair.sign(payload).0.to_vec(),
+        }
+    }
+
+    /// Signs a 32-byte message hash with an ECDSA keypair and returns the 65-byte recoverable
+    /// signature. Returns `None` for other schemes.
+    pub fn sign_prehashed(&self, message_hash: &[u8; 32]) -> Option<[u8; 65]> {
+        match self {
+            Self::Ecdsa(pair) => Some(pair.sign_prehashed(message_hash).0),
+            _ => None,
+        }
+    }
+
+    /// The Ethereum address of an ECDSA keypair, i.e. the last 20 bytes of the keccak-256 hash of
+    /// the uncompressed public key. Returns `None` for other schemes.
+    pub fn eth_address(&self) -> Option<[u8; 20]> {
+        let Self::Ecdsa(pair) = self else {
+            return None;
+        };
+        // `sp-core` exposes only the compressed public key, so we recover the uncompressed one
+        // from a signature.
+        let message_hash = [0u8; 32];
+        let signature = pair.sign_prehashed(&message_hash);
+        let Ok(uncompressed) = sp_io::crypto::secp256k1_ecdsa_recover(&signature.0, &message_hash)
+        else {
+            unreachable!("Signature has just been created by the keypair");
+        };
+        let hash = keccak_256(&uncompressed);
+
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&hash[12..]);
+        Some(address)
+    }
+}
+
+/// A named test account.
+#[derive(Clone)]
+pub struct Account {
+    name: String,
+    keypair: KeyPair,
+}
+
+impl Account {
+    /// The name under which the account is registered.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The keypair of the account.
+    pub fn keypair(&self) -> &KeyPair {
+        &self.keypair
+    }
+
+    /// The account id of the account.
+    pub fn account_id(&self) -> AccountId32 {
+        self.keypair.account_id()
+    }
+}
+
+/// Registry of the named accounts of a session.
+#[derive(Clone, Default)]
+pub(crate) struct AccountRegistry {
+    accounts: BTreeMap<String, Account>,
+    anonymous: u32,
+}
+
+impl AccountRegistry {
+    /// Returns the account named `name`, registering it with a keypair of `scheme` if needed.
+    ///
+    /// An already registered account is returned as is, regardless of `scheme`.
+    pub fn get_or_register(
+        &mut self,
+        name: &str,
+        scheme: KeyScheme,
+    ) -> Result<&Account, SecretStringError> {
+        if !self.accounts.contains_key(name) {
+            let account = Account {
+                name: name.to_string(),
+                keypair: KeyPair::from_name(name, scheme)?,
+            };
+            self.accounts.insert(name.to_string(), account);
+        }
+        Ok(&self.accounts[name])
+    }
+
+    /// Returns a fresh name, not used by any registered account.
+    pub fn fresh_name(&mut self) -> String {
+        loop {
+            self.anonymous += 1;
+            let name = format!("account-{}", self.anonymous);
+            if !self.accounts.contains_key(&name) {
+                return name;
+            }
+        }
+    }
+
+    /// Returns the account named `name`, if registered.
+    pub fn get(&self, name: &str) -> Option<&Account> {
+        self.accounts.get(name)
+    }
+
+    /// Iterates over all the registered accounts, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = &Account> {
+        self.accounts.values()
+    }
+
+    /// Replaces every argument of the form `@<name>`, where `<name>` is a registered account,
+    /// with the SS58 address of that account, so that it can be passed to the transcoder. All the
+    /// other arguments (including plain account names) are left as they are.
+    pub fn resolve_args<S: AsRef<str>>(&self, args: &[S]) -> Vec<String> {
+        args.iter()
+            .map(|arg| {
+                let arg = arg.as_ref();
+                arg.strip_prefix(ACCOUNT_REF_PREFIX)
+                    .and_then(|name| self.accounts.get(name))
+                    .map(|account| account.account_id().to_ss58check())
+                    .unwrap_or_else(|| arg.to_string())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sp_core::crypto::Ss58Codec;
+
+    use super::*;
+
+    fn verify(keypair: &KeyPair, payload: &[u8], signature: &[u8]) -> bool {
+        match keypair {
+            KeyPair::Sr25519(pair) => sr25519::Pair::verify(
+                &sr25519::Signature::from_slice(signature).unwrap(),
+                payload,
+                &pair.public(),
+            ),
+            KeyPair::Ed25519(pair) => ed25519::Pair::verify(
+                &ed25519::Signature::from_slice(signature).unwrap(),
+                payload,
+                &pair.public(),
+            ),
+            KeyPair::Ecdsa(pair) => ecdsa::Pair::verify(
+                &ecdsa::Signature::from_slice(signature).unwrap(),
+                payload,
+                &pair.public(),
+            ),
+        }
+    }
+
+    #[test]
+    fn signatures_can_be_verified() {
+        for scheme in [KeyScheme::Sr25519, KeyScheme::Ed25519, KeyScheme::Ecdsa] {
+            let keypair = KeyPair::from_name("Alice", scheme).unwrap();
+            let signature = keypair.sign(b"permit");
+            assert!(verify(&keypair, b"permit", &signature));
+            assert!(!verify(&keypair, b"other", &signature));
+        }
+    }
+
+    #[test]
+    fn ecdsa_signatures_recover_to_public_key() {
+        let keypair = KeyPair::from_name("Alice", KeyScheme::Ecdsa).unwrap();
+        let message_hash = keccak_256(b"meta-transaction");
+        let signature = keypair.sign_prehashed(&message_hash).unwrap();
+
+        let recovered =
+            sp_io::crypto::secp256k1_ecdsa_recover_compressed(&signature, &message_hash);
+        assert_eq!(recovered.ok().map(Vec::from), Some(keypair.public_key()));
+    }
+
+    #[test]
+    fn eth_address_is_derived_from_public_key() {
+        let mut secret = [0u8; 32];
+        secret[31] = 1;
+        let keypair = KeyPair::Ecdsa(ecdsa::Pair::from_seed(&secret));
+
+        assert_eq!(
+            keypair.eth_address().unwrap().to_vec(),
+            [
+                0x7e, 0x5f, 0x45, 0x52, 0x09, 0x1a, 0x69, 0x12, 0x5d, 0x5d, 0xfc, 0xb7, 0xb8, 0xc2,
+                0x65, 0x90, 0x29, 0x39, 0x5b, 0xdf
+            ]
+            .to_vec()
+        );
+        assert!(KeyPair::from_name("Alice", KeyScheme::Sr25519)
+            .unwrap()
+            .eth_address()
+            .is_none());
+    }
+
+    #[test]
+    fn keypairs_are_derived_deterministically() {
+        let alice = KeyPair::from_name("Alice", KeyScheme::Sr25519).unwrap();
+        assert_eq!(
+            alice.account_id().to_ss58check(),
+            "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+        );
+
+        let ed_alice = KeyPair::from_name("Alice", KeyScheme::Ed25519).unwrap();
+        assert_ne!(ed_alice.account_id(), alice.account_id());
+        assert_eq!(
+            KeyPair::from_name("Alice", KeyScheme::Ed25519)
+                .unwrap()
+                .account_id(),
+            ed_alice.account_id()
+        );
+    }
+
+    #[test]
+    fn registered_names_are_resolved_in_args() {
+        let mut registry = AccountRegistry::default();
+        let bob = registry
+            .get_or_register("bob", KeyScheme::Sr25519)
+            .unwrap()
+            .account_id();
+
+        assert_eq!(
+            registry.resolve_args(&["@bob", "@charlie", "42"]),
+            vec![bob.to_ss58check(), "@charlie".to_string(), "42".to_string()]
+        );
+    }
+
+    #[test]
+    fn plain_names_are_not_resolved_in_args() {
+        let mut registry = AccountRegistry::default();
+        registry.get_or_register("bob", KeyScheme::Sr25519).unwrap();
+
+        // A string argument that happens to equal an account name is passed as it is.
+        assert_eq!(registry.resolve_args(&["bob"]), vec!["bob".to_string()]);
+    }
+
+    #[test]
+    fn fresh_names_skip_registered_ones() {
+        let mut registry = AccountRegistry::default();
+        registry
+            .get_or_register("account-1", KeyScheme::Sr25519)
+            .unwrap();
+
+        assert_eq!(registry.fresh_name(), "account-2");
+        assert_eq!(registry.fresh_name(), "account-3");
+    }
+}
@@ -3,6 +3,8 @@
 use frame_support::sp_runtime::DispatchError;
 use thiserror::Error;
 
+use crate::errors::LangError;
+
 /// Session specific errors.
 #[derive(Error, Debug)]
 pub enum SessionError {
@@ -36,4 +38,28 @@ pub enum SessionError {
     /// There is no registered transcoder to encode/decode messages for the called contract.
     #[error("Missing transcoder")]
     NoTranscoder,
+    /// The name cannot be used to derive an account keypair.
+    #[error("Invalid account name {0}")]
+    InvalidAccountName(String),
+    /// There is no registered account with the given name.
+    #[error("No account named {0}")]
+    NoAccount(String),
+    /// Minting tokens to an account failed.
+    #[error("Funding account failed: {0:?}")]
+    FundingFailed(DispatchError),
+    /// Code removal failed (aborted by the pallet).
+    #[error("Code removal failed: {0:?}")]
+    RemoveCodeFailed(DispatchError),
+    /// Replacing the code of a contract failed.
+    #[error("Contract upgrade failed: {0:?}")]
+    UpgradeFailed(DispatchError),
+    /// The upgrade message of the contract failed before it could replace the code.
+    #[error("Upgrade message failed: {0:?}")]
+    UpgradeMessageFailed(LangError),
+    /// The contract storage does not decode with the storage layout of the contract.
+    #[error("Contract storage does not match the layout: {0}")]
+    IncompatibleStorage(String),
+    /// Saving, loading or replaying a transcript failed.
+    #[error("Transcript error: {0}")]
+    Transcript(String),
 }
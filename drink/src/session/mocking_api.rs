@@ -1,5 +1,5 @@
 //! Mocking API for the sandbox.
-use super::Session;
+use super::{transcript::TranscriptEntry, Session};
 use crate::{mock::ContractMock, runtime::AccountIdFor, SandboxConfig, DEFAULT_GAS_LIMIT};
 
 /// Interface for basic mocking operations.
@@ -47,6 +47,11 @@ where
             .expect("Should be able to acquire lock on registry")
             .register(mock_address.clone(), mock);
 
+        self.record
+            .push_transcript_entry(TranscriptEntry::DeployMock {
+                address: mock_address.clone(),
+            });
+
         mock_address
     }
 
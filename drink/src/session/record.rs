@@ -1,18 +1,33 @@
-use std::rc::Rc;
+use std::{cell::OnceCell, rc::Rc};
 
-use contract_transcode::{ContractMessageTranscoder, Value};
+use contract_transcode::{ContractMessageTranscoder, Map, Value};
+use frame_support::traits::PalletInfo;
 use parity_scale_codec::{Decode, Encode};
 
 use crate::{
     errors::MessageResult,
-    runtime::{minimal::RuntimeEvent, AccountIdFor, MinimalRuntime},
-    session::{error::SessionError, BalanceOf},
-    EventRecordOf,
+    runtime::{AccountIdFor, HashFor, RuntimeEventOf},
+    session::{
+        error::SessionError,
+        transcoding::TranscoderRegistry,
+        transcript::{Transcript, TranscriptEntry},
+        BalanceOf,
+    },
+    EventRecordOf, RuntimeCall,
 };
 
-type ContractInstantiateResult<R> =
+mod assertions;
+mod deposits;
+mod json;
+mod terminations;
+
+pub use deposits::{DepositLedger, DepositMovement};
+pub use terminations::Termination;
+
+pub(super) type ContractInstantiateResult<R> =
     pallet_contracts::ContractInstantiateResult<AccountIdFor<R>, BalanceOf<R>, EventRecordOf<R>>;
-type ContractExecResult<R> = pallet_contracts::ContractExecResult<BalanceOf<R>, EventRecordOf<R>>;
+pub(super) type ContractExecResult<R> =
+    pallet_contracts::ContractExecResult<BalanceOf<R>, EventRecordOf<R>>;
 
 /// Data structure storing the results of contract interaction during a session.
 ///
@@ -36,6 +51,17 @@ pub struct Record<Config: pallet_contracts::Config> {
 
     /// The events emitted by the contracts.
     event_batches: Vec<EventBatch<Config>>,
+
+    /// Replayable log of all the interactions.
+    transcript: Transcript<Config>,
+
+    /// Transcoders used to encode and decode the messages of the known contracts.
+    transcoders: TranscoderRegistry<AccountIdFor<Config>>,
+    /// Transcoders of the contracts terminated during the session, kept for decoding the recorded
+    /// interactions with them.
+    terminated_transcoders: TranscoderRegistry<AccountIdFor<Config>>,
+    /// Transcoders of the known codes, used for instantiating contracts from uploaded code.
+    code_transcoders: TranscoderRegistry<HashFor<Config>>,
 }
 
 // API for `Session` to record results and events related to contract interaction.
@@ -56,8 +82,63 @@ impl<Config: pallet_contracts::Config> Record<Config> {
         self.call_returns.push(return_value);
     }
 
-    pub(super) fn push_event_batches(&mut self, events: Vec<EventRecordOf<Config>>) {
-        self.event_batches.push(EventBatch { events });
+    pub(super) fn push_event_batches(
+        &mut self,
+        events: Vec<EventRecordOf<Config>>,
+        runtime_calls: Vec<DispatchedCall<Config>>,
+    ) {
+        self.event_batches.push(EventBatch {
+            events,
+            runtime_calls,
+            emitted: OnceCell::new(),
+        });
+    }
+
+    pub(super) fn push_transcript_entry(&mut self, entry: TranscriptEntry<Config>) {
+        self.transcript.push(entry);
+    }
+
+    pub(super) fn register_transcoder(
+        &mut self,
+        address: AccountIdFor<Config>,
+        transcoder: &Rc<ContractMessageTranscoder>,
+    ) {
+        self.transcoders.register(address, transcoder);
+    }
+
+    pub(super) fn register_code_transcoder(
+        &mut self,
+        code_hash: HashFor<Config>,
+        transcoder: &Rc<ContractMessageTranscoder>,
+    ) {
+        self.code_transcoders.register(code_hash, transcoder);
+    }
+
+    pub(super) fn code_transcoder(
+        &self,
+        code_hash: &HashFor<Config>,
+    ) -> Option<Rc<ContractMessageTranscoder>> {
+        self.code_transcoders.get(code_hash)
+    }
+
+    /// Unregisters the transcoder of the terminated contract under `address`. It is still used for
+    /// decoding the recorded events and return values of the contract.
+    pub(super) fn forget_transcoder(&mut self, address: &AccountIdFor<Config>) {
+        if let Some(transcoder) = self.transcoders.remove(address) {
+            self.terminated_transcoders
+                .register(address.clone(), &transcoder);
+        }
+    }
+
+    /// Returns the transcoder for decoding the recorded data of the contract under `address`, also
+    /// if the contract has been terminated since.
+    fn decoding_transcoder(
+        &self,
+        address: &AccountIdFor<Config>,
+    ) -> Option<Rc<ContractMessageTranscoder>> {
+        self.transcoders
+            .get(address)
+            .or_else(|| self.terminated_transcoders.get(address))
     }
 }
 
@@ -129,11 +210,90 @@ impl<Config: pallet_contracts::Config> Record<Config> {
     pub fn last_event_batch(&self) -> &EventBatch<Config> {
         self.event_batches.last().expect("No event batches")
     }
+
+    /// Returns the transcript of the session, which can be saved and later replayed with
+    /// `Session::replay`.
+    pub fn transcript(&self) -> &Transcript<Config> {
+        &self.transcript
+    }
+
+    /// Returns the transcoder registered for the contract under `address`, if any.
+    pub fn transcoder(
+        &self,
+        address: &AccountIdFor<Config>,
+    ) -> Option<Rc<ContractMessageTranscoder>> {
+        self.transcoders.get(address)
+    }
+}
+
+impl<Config: pallet_contracts::Config> Record<Config>
+where
+    RuntimeEventOf<Config>: TryInto<pallet_contracts::Event<Config>>,
+{
+    /// Returns all the events emitted by the contract under `address` during the session (across
+    /// all event batches), decoded with the transcoder registered for that contract.
+    ///
+    /// Anonymous events are matched by decoding their declared fields.
+    pub fn decoded_events_of(
+        &self,
+        address: &AccountIdFor<Config>,
+    ) -> Result<Vec<Value>, SessionError> {
+        let transcoder = self
+            .decoding_transcoder(address)
+            .ok_or(SessionError::NoTranscoder)?;
+        self.event_batches
+            .iter()
+            .flat_map(|batch| batch.emitted_events_of(address))
+            .map(|event| {
+                decode_event_of_contract(&transcoder, event).ok_or_else(|| {
+                    SessionError::Decoding(format!("Failed to decode event emitted by {address:?}"))
+                })
+            })
+            .collect()
+    }
+}
+
+/// A runtime call that a contract tried to dispatch (with `call_runtime`).
+#[derive(
+    frame_support::CloneNoBound, frame_support::DebugNoBound, frame_support::PartialEqNoBound,
+)]
+pub struct DispatchedCall<R: frame_system::Config> {
+    /// The dispatched call, SCALE-encoded.
+    pub encoded: Vec<u8>,
+    /// The dispatched call, or `None` if `encoded` is not a valid `RuntimeCall` of `R` (e.g. when
+    /// the call filter is shared with a different runtime).
+    pub call: Option<RuntimeCall<R>>,
+    /// Whether the call passed the call filter (and thus was actually executed).
+    pub allowed: bool,
+}
+
+impl<R: frame_system::Config> DispatchedCall<R> {
+    pub(super) fn new(encoded: Vec<u8>, allowed: bool) -> Self {
+        Self {
+            call: Decode::decode(&mut &encoded[..]).ok(),
+            encoded,
+            allowed,
+        }
+    }
+}
+
+/// A contract event (`pallet_contracts::Event::ContractEmitted`) extracted from an event record.
+struct EmittedEvent<R: frame_system::Config> {
+    /// The emitting contract.
+    contract: AccountIdFor<R>,
+    /// The data of the event, as emitted by the contract.
+    data: Vec<u8>,
+    /// The first topic of the event record. Unless the event is anonymous, it is the signature
+    /// topic of the event.
+    first_topic: Option<HashFor<R>>,
 }
 
 /// A batch of runtime events that were emitted during a single contract interaction.
 pub struct EventBatch<R: frame_system::Config> {
     events: Vec<EventRecordOf<R>>,
+    runtime_calls: Vec<DispatchedCall<R>>,
+    /// Contract events extracted from `events`, computed on first use.
+    emitted: OnceCell<Vec<EmittedEvent<R>>>,
 }
 
 impl<R: frame_system::Config> EventBatch<R> {
@@ -141,64 +301,254 @@ impl<R: frame_system::Config> EventBatch<R> {
     pub fn all_events(&self) -> &[EventRecordOf<R>] {
         &self.events
     }
+
+    /// Returns all the runtime calls that the contracts tried to dispatch during the contract
+    /// interaction, in the order of dispatching.
+    pub fn runtime_calls(&self) -> &[DispatchedCall<R>] {
+        &self.runtime_calls
+    }
 }
 
-impl EventBatch<MinimalRuntime> {
+impl<R: pallet_contracts::Config> EventBatch<R> {
+    /// Returns the addresses of the contracts terminated during the contract interaction.
+    ///
+    /// Unlike the other event accessors, it works for every runtime (so that `Session` can use it
+    /// without additional bounds): the contracts pallet events are found by the index of the
+    /// pallet in the runtime.
+    pub(super) fn terminated_contracts(&self) -> Vec<AccountIdFor<R>> {
+        let Some(index) =
+            <R as frame_system::Config>::PalletInfo::index::<pallet_contracts::Pallet<R>>()
+        else {
+            return vec![];
+        };
+
+        self.events
+            .iter()
+            .filter_map(|record| {
+                let encoded = record.event.encode();
+                let (&pallet, mut event) = encoded.split_first()?;
+                match pallet_contracts::Event::<R>::decode(&mut event) {
+                    Ok(pallet_contracts::Event::Terminated { contract, .. })
+                        if pallet as usize == index =>
+                    {
+                        Some(contract)
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
+
+impl<R: pallet_contracts::Config> EventBatch<R>
+where
+    RuntimeEventOf<R>: TryInto<pallet_contracts::Event<R>>,
+{
+    /// Returns all the contract events (together with the emitting contracts) that were emitted
+    /// during the contract interaction.
+    ///
+    /// Contract events can be extracted for every runtime whose event type can be converted into
+    /// `pallet_contracts::Event<R>`, which `construct_runtime!` provides out of the box.
+    fn emitted_events(&self) -> &[EmittedEvent<R>] {
+        self.emitted.get_or_init(|| {
+            self.events
+                .iter()
+                .filter_map(|record| {
+                    match TryInto::<pallet_contracts::Event<R>>::try_into(record.event.clone()) {
+                        Ok(pallet_contracts::Event::ContractEmitted { contract, data }) => {
+                            Some(EmittedEvent {
+                                contract,
+                                data,
+                                first_topic: record.topics.first().copied(),
+                            })
+                        }
+                        _ => None,
+                    }
+                })
+                .collect()
+        })
+    }
+
+    /// Returns the contract events that were emitted by the contract under `address` during the
+    /// contract interaction.
+    fn emitted_events_of<'a>(
+        &'a self,
+        address: &AccountIdFor<R>,
+    ) -> impl Iterator<Item = &'a EmittedEvent<R>> + 'a {
+        let address = address.clone();
+        self.emitted_events()
+            .iter()
+            .filter(move |event| event.contract == address)
+    }
+
     /// Returns all the contract events that were emitted during the contract interaction.
     ///
     /// **WARNING**: This method will return all the events that were emitted by ANY contract. If your
     /// call triggered multiple contracts, you will have to filter the events yourself.
-    ///
-    /// We have to match against static enum variant, and thus (at least for now) we support only
-    /// `MinimalRuntime`.
     pub fn contract_events(&self) -> Vec<&[u8]> {
-        self.events
+        self.emitted_events()
             .iter()
-            .filter_map(|event| match &event.event {
-                RuntimeEvent::Contracts(
-                    pallet_contracts::Event::<MinimalRuntime>::ContractEmitted { data, .. },
-                ) => Some(data.as_slice()),
-                _ => None,
-            })
+            .map(|event| event.data.as_slice())
+            .collect()
+    }
+
+    /// Returns the contract events that were emitted by the contract under `address` during the
+    /// contract interaction.
+    pub fn events_of(&self, address: &AccountIdFor<R>) -> Vec<&[u8]> {
+        self.emitted_events_of(address)
+            .map(|event| event.data.as_slice())
             .collect()
     }
 
-    /// The same as `contract_events`, but decodes the events using the given transcoder.
+    /// The same as `contract_events`, but decodes the events using the given transcoder. Every event
+    /// is decoded according to the event spec matching its signature topic.
     ///
     /// **WARNING**: This method will try to decode all the events that were emitted by ANY
-    /// contract. This means that some contract events might either fail to decode or be decoded
-    /// incorrectly (to some rubbish). In the former case, they will be skipped, but with the latter
-    /// case, you will have to filter the events yourself.
+    /// contract. Events whose signature topic is unknown to the transcoder are skipped, but the
+    /// events of other contracts might still match by chance, so you may have to filter the
+    /// events yourself.
     ///
-    /// **WARNING 2**: This method will ignore anonymous events.
+    /// **WARNING 2**: This method will ignore anonymous events. If you know the emitting contract,
+    /// use `events_of` or `Record::decoded_events_of` instead.
     pub fn contract_events_decoded(
         &self,
         transcoder: &Rc<ContractMessageTranscoder>,
     ) -> Vec<Value> {
-        let signature_topics = transcoder
-            .metadata()
-            .spec()
-            .events()
+        self.emitted_events()
             .iter()
-            .filter_map(|event| event.signature_topic())
-            .map(|sig| sig.as_bytes().try_into().unwrap())
-            .collect::<Vec<[u8; 32]>>();
-
-        self.contract_events()
-            .into_iter()
-            .filter_map(|data| {
-                for signature_topic in &signature_topics {
-                    if let Ok(decoded) = transcoder
-                        // We have to `encode` the data because `decode_contract_event` is targeted
-                        // at decoding the data from the runtime, and not directly from the contract
-                        // events.
-                        .decode_contract_event(&signature_topic, &mut &*data.encode())
-                    {
-                        return Some(decoded);
-                    }
-                }
-                None
+            .filter_map(|event| {
+                let signature_topic = signature_topic(transcoder, event)?;
+                decode_contract_event(transcoder, &signature_topic, &event.data)
             })
             .collect()
     }
 }
+
+/// Returns the first topic of `event` if it is the signature topic of one of the events known to
+/// `transcoder`.
+fn signature_topic<R: frame_system::Config>(
+    transcoder: &ContractMessageTranscoder,
+    event: &EmittedEvent<R>,
+) -> Option<[u8; 32]> {
+    let first_topic: &[u8] = event.first_topic.as_ref()?.as_ref();
+    transcoder
+        .metadata()
+        .spec()
+        .events()
+        .iter()
+        .filter_map(|spec| spec.signature_topic())
+        .find(|sig| sig.as_bytes() == first_topic)
+        .map(|sig| sig.as_bytes().try_into().unwrap())
+}
+
+/// Decodes the data of a contract event according to the event spec with `signature_topic`.
+fn decode_contract_event(
+    transcoder: &ContractMessageTranscoder,
+    signature_topic: &[u8; 32],
+    data: &[u8],
+) -> Option<Value> {
+    transcoder
+        // We have to `encode` the data because `decode_contract_event` is targeted at decoding the
+        // data from the runtime, and not directly from the contract events.
+        .decode_contract_event(signature_topic, &mut &*data.encode())
+        .ok()
+}
+
+/// Tries to decode the data of an anonymous contract event: the data must consist exactly of the
+/// encoded fields declared for one of the anonymous events known to `transcoder`.
+fn decode_anonymous_event(transcoder: &ContractMessageTranscoder, data: &[u8]) -> Option<Value> {
+    transcoder
+        .metadata()
+        .spec()
+        .events()
+        .iter()
+        .filter(|event| event.signature_topic().is_none())
+        .find_map(|event| {
+            let mut input = data;
+            let mut fields = vec![];
+            for arg in event.args() {
+                let value = transcoder.decode(arg.ty().ty().id, &mut input).ok()?;
+                fields.push((Value::String(arg.label().to_string()), value));
+            }
+            input.is_empty().then(|| {
+                Value::Map(Map::new(
+                    Some(event.label().as_str()),
+                    fields.into_iter().collect(),
+                ))
+            })
+        })
+}
+
+/// Decodes an event emitted by a contract, which is known to be described by `transcoder`. The
+/// event spec is selected by the signature topic of the event. Events without a known signature
+/// topic are decoded as anonymous events.
+fn decode_event_of_contract<R: frame_system::Config>(
+    transcoder: &ContractMessageTranscoder,
+    event: &EmittedEvent<R>,
+) -> Option<Value> {
+    match signature_topic(transcoder, event) {
+        Some(signature_topic) => decode_contract_event(transcoder, &signature_topic, &event.data),
+        None => decode_anonymous_event(transcoder, &event.data),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::OnceCell;
+
+    use frame_system::{EventRecord, Phase};
+    use parity_scale_codec::Encode;
+
+    use super::{DispatchedCall, EventBatch};
+    use crate::{
+        minimal::{RuntimeCall, RuntimeEvent},
+        AccountId32, EventRecordOf, MinimalRuntime,
+    };
+
+    /// Wraps `event` into a record, as if it was emitted during block initialization. Shared with
+    /// the tests of the submodules.
+    pub(super) fn record_of(event: RuntimeEvent) -> EventRecordOf<MinimalRuntime> {
+        EventRecord {
+            phase: Phase::Initialization,
+            event,
+            topics: vec![],
+        }
+    }
+
+    fn emitted(contract: AccountId32, data: Vec<u8>) -> EventRecordOf<MinimalRuntime> {
+        record_of(RuntimeEvent::Contracts(
+            pallet_contracts::Event::ContractEmitted { contract, data },
+        ))
+    }
+
+    #[test]
+    fn events_are_filtered_by_emitting_contract() {
+        let alice_contract = AccountId32::new([1u8; 32]);
+        let bob_contract = AccountId32::new([2u8; 32]);
+
+        let batch = EventBatch::<MinimalRuntime> {
+            events: vec![
+                emitted(alice_contract.clone(), vec![1]),
+                emitted(bob_contract.clone(), vec![2]),
+                emitted(alice_contract.clone(), vec![3]),
+            ],
+            runtime_calls: vec![],
+            emitted: OnceCell::new(),
+        };
+
+        assert_eq!(batch.contract_events(), vec![&[1u8][..], &[2], &[3]]);
+        assert_eq!(batch.events_of(&alice_contract), vec![&[1u8][..], &[3]]);
+        assert_eq!(batch.events_of(&bob_contract), vec![&[2u8][..]]);
+    }
+
+    #[test]
+    fn undecodable_runtime_calls_are_kept_encoded() {
+        let remark = RuntimeCall::System(frame_system::Call::remark { remark: vec![1] });
+        let decoded = DispatchedCall::<MinimalRuntime>::new(remark.encode(), true);
+        assert_eq!(decoded.call, Some(remark));
+
+        let garbage = DispatchedCall::<MinimalRuntime>::new(vec![u8::MAX; 4], false);
+        assert_eq!(garbage.call, None);
+        assert_eq!(garbage.encoded, vec![u8::MAX; 4]);
+    }
+}
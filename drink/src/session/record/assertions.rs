@@ -0,0 +1,197 @@
+//! Assertions on the contract events recorded during a session.
+
+use contract_transcode::Value;
+
+use super::{decode_event_of_contract, Record};
+use crate::runtime::{AccountIdFor, RuntimeEventOf};
+
+impl<Config: pallet_contracts::Config> Record<Config>
+where
+    RuntimeEventOf<Config>: TryInto<pallet_contracts::Event<Config>>,
+{
+    /// Asserts that the contract under `address` emitted an event named `event` in the last event
+    /// batch, with (at least) the given `fields`.
+    ///
+    /// Field values are compared in the notation used by the transcoder (the same as for message
+    /// arguments), e.g. `[("from", "Some(5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY)"),
+    /// ("value", "100")]`. Fields that are not listed are not checked.
+    ///
+    /// Panics with the list of events actually emitted by the contract if there is no such event.
+    #[track_caller]
+    pub fn assert_emitted<'a>(
+        &self,
+        address: &AccountIdFor<Config>,
+        event: &str,
+        fields: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) {
+        let fields = fields.into_iter().collect::<Vec<_>>();
+        let emitted = self.last_batch_events_of(address);
+
+        if emitted
+            .iter()
+            .any(|decoded| is_match(decoded, event, &fields))
+        {
+            return;
+        }
+
+        let mut report = format!(
+            "Expected event `{event}` {} to be emitted by {address:?} in the last event batch.\n",
+            describe_fields(&fields)
+        );
+        report.push_str(&describe_emitted(&emitted, event, &fields));
+        panic!("{report}");
+    }
+
+    /// Asserts that the contract under `address` did not emit any event named `event` with (at
+    /// least) the given `fields` in the last event batch. With no fields, any event named `event`
+    /// fails the assertion.
+    ///
+    /// Panics with the list of events actually emitted by the contract if there is such an event.
+    #[track_caller]
+    pub fn assert_not_emitted<'a>(
+        &self,
+        address: &AccountIdFor<Config>,
+        event: &str,
+        fields: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) {
+        let fields = fields.into_iter().collect::<Vec<_>>();
+        let emitted = self.last_batch_events_of(address);
+
+        if !emitted
+            .iter()
+            .any(|decoded| is_match(decoded, event, &fields))
+        {
+            return;
+        }
+
+        let mut report = format!(
+            "Expected no event `{event}` {} to be emitted by {address:?} in the last event batch.\n",
+            describe_fields(&fields)
+        );
+        report.push_str(&describe_emitted(&emitted, event, &fields));
+        panic!("{report}");
+    }
+
+    /// Decodes the events emitted by the contract under `address` in the last event batch.
+    /// Events that cannot be decoded are returned as `Err` with their raw data.
+    #[track_caller]
+    fn last_batch_events_of(&self, address: &AccountIdFor<Config>) -> Vec<Result<Value, Vec<u8>>> {
+        let transcoder = self
+            .decoding_transcoder(address)
+            .unwrap_or_else(|| panic!("No transcoder registered for {address:?}"));
+
+        self.last_event_batch()
+            .emitted_events_of(address)
+            .map(|event| {
+                decode_event_of_contract(&transcoder, event).ok_or_else(|| event.data.clone())
+            })
+            .collect()
+    }
+}
+
+/// Checks whether a decoded event is named `event` and has (at least) the given `fields`.
+fn is_match(decoded: &Result<Value, Vec<u8>>, event: &str, fields: &[(&str, &str)]) -> bool {
+    field_mismatches(decoded, event, fields).is_some_and(|mismatches| mismatches.is_empty())
+}
+
+/// Compares a decoded event against the expectation. Returns `None` if the event has a different
+/// name. Otherwise, returns descriptions of all the mismatching fields.
+fn field_mismatches(
+    decoded: &Result<Value, Vec<u8>>,
+    event: &str,
+    fields: &[(&str, &str)],
+) -> Option<Vec<String>> {
+    let Ok(Value::Map(map)) = decoded else {
+        return None;
+    };
+    if map.ident().as_deref() != Some(event) {
+        return None;
+    }
+
+    Some(
+        fields
+            .iter()
+            .filter_map(|(name, expected)| match map.get_by_str(name) {
+                None => Some(format!("`{name}`: missing")),
+                Some(actual) if actual.to_string() != *expected => {
+                    Some(format!("`{name}`: expected {expected}, got {actual}"))
+                }
+                Some(_) => None,
+            })
+            .collect(),
+    )
+}
+
+fn describe_fields(fields: &[(&str, &str)]) -> String {
+    if fields.is_empty() {
+        return "(with any fields)".to_string();
+    }
+    let fields = fields
+        .iter()
+        .map(|(name, value)| format!("{name}: {value}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{{ {fields} }}")
+}
+
+fn describe_emitted(
+    emitted: &[Result<Value, Vec<u8>>],
+    event: &str,
+    fields: &[(&str, &str)],
+) -> String {
+    if emitted.is_empty() {
+        return "The contract emitted no events.".to_string();
+    }
+
+    let mut description = "Events emitted by the contract:\n".to_string();
+    for decoded in emitted {
+        match decoded {
+            Ok(value) => description.push_str(&format!("  - {value}\n")),
+            Err(data) => description.push_str(&format!("  - <undecodable event: {data:?}>\n")),
+        }
+        for mismatch in field_mismatches(decoded, event, fields).unwrap_or_default() {
+            description.push_str(&format!("      {mismatch}\n"));
+        }
+    }
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use contract_transcode::{Map, Value};
+
+    use super::field_mismatches;
+
+    fn transfer(value: u128) -> Result<Value, Vec<u8>> {
+        Ok(Value::Map(Map::new(
+            Some("Transfer"),
+            vec![(Value::String("value".to_string()), Value::UInt(value))]
+                .into_iter()
+                .collect(),
+        )))
+    }
+
+    #[test]
+    fn matching_event_has_no_mismatches() {
+        assert_eq!(
+            field_mismatches(&transfer(100), "Transfer", &[("value", "100")]),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn differently_named_event_does_not_match() {
+        assert_eq!(field_mismatches(&transfer(100), "Approval", &[]), None);
+    }
+
+    #[test]
+    fn mismatching_fields_are_described() {
+        assert_eq!(
+            field_mismatches(&transfer(50), "Transfer", &[("value", "100"), ("to", "0")]),
+            Some(vec![
+                "`value`: expected 100, got 50".to_string(),
+                "`to`: missing".to_string()
+            ])
+        );
+    }
+}
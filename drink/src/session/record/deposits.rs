@@ -0,0 +1,315 @@
+//! Storage deposit accounting of the contracts interacted with during the session.
+
+use std::collections::BTreeMap;
+
+use frame_support::{
+    sp_runtime::traits::{Saturating, Zero},
+    traits::PalletInfo,
+    CloneNoBound, DebugNoBound, DefaultNoBound,
+};
+use pallet_contracts::StorageDeposit;
+use parity_scale_codec::Decode;
+
+use super::Record;
+use crate::{
+    runtime::AccountIdFor,
+    session::{transcript::TranscriptEntry, BalanceOf},
+};
+
+/// A single storage deposit movement, caused by a deployment or a call.
+#[derive(CloneNoBound, DebugNoBound)]
+pub struct DepositMovement<Config: pallet_contracts::Config> {
+    /// The actor of the interaction, who paid the charge or received the refund.
+    pub payer: AccountIdFor<Config>,
+    /// The charged or refunded amount.
+    pub deposit: StorageDeposit<BalanceOf<Config>>,
+}
+
+/// Storage deposit movements of a single contract across the session.
+#[derive(CloneNoBound, DebugNoBound, DefaultNoBound)]
+pub struct DepositLedger<Config: pallet_contracts::Config> {
+    movements: Vec<DepositMovement<Config>>,
+}
+
+impl<Config: pallet_contracts::Config> DepositLedger<Config> {
+    /// All the movements, in the order of execution.
+    pub fn movements(&self) -> &[DepositMovement<Config>] {
+        &self.movements
+    }
+
+    /// The total amount charged.
+    pub fn charged(&self) -> BalanceOf<Config> {
+        self.sum(|_| true, charge_of)
+    }
+
+    /// The total amount refunded.
+    pub fn refunded(&self) -> BalanceOf<Config> {
+        self.sum(|_| true, refund_of)
+    }
+
+    /// The total amount charged to `payer`.
+    pub fn charged_to(&self, payer: &AccountIdFor<Config>) -> BalanceOf<Config> {
+        self.sum(|movement| &movement.payer == payer, charge_of)
+    }
+
+    /// The total amount refunded to `payer`.
+    pub fn refunded_to(&self, payer: &AccountIdFor<Config>) -> BalanceOf<Config> {
+        self.sum(|movement| &movement.payer == payer, refund_of)
+    }
+
+    /// All the accounts that paid a charge or received a refund, without duplicates.
+    pub fn payers(&self) -> Vec<AccountIdFor<Config>> {
+        let mut payers: Vec<_> = self
+            .movements
+            .iter()
+            .map(|movement| movement.payer.clone())
+            .collect();
+        payers.sort();
+        payers.dedup();
+        payers
+    }
+
+    fn sum(
+        &self,
+        filter: impl Fn(&DepositMovement<Config>) -> bool,
+        amount: fn(&StorageDeposit<BalanceOf<Config>>) -> BalanceOf<Config>,
+    ) -> BalanceOf<Config> {
+        self.movements
+            .iter()
+            .filter(|movement| filter(movement))
+            .fold(Zero::zero(), |total: BalanceOf<Config>, movement| {
+                total.saturating_add(amount(&movement.deposit))
+            })
+    }
+}
+
+fn charge_of<Balance: Copy + Zero>(deposit: &StorageDeposit<Balance>) -> Balance {
+    match deposit {
+        StorageDeposit::Charge(amount) => *amount,
+        StorageDeposit::Refund(_) => Zero::zero(),
+    }
+}
+
+fn refund_of<Balance: Copy + Zero>(deposit: &StorageDeposit<Balance>) -> Balance {
+    match deposit {
+        StorageDeposit::Refund(amount) => *amount,
+        StorageDeposit::Charge(_) => Zero::zero(),
+    }
+}
+
+impl<Config: pallet_contracts::Config> Record<Config> {
+    /// Returns the storage deposit ledgers of all the contracts deployed or called during the
+    /// session, keyed by the contract address.
+    ///
+    /// The whole deposit of an interaction is attributed to the contract it targeted, including
+    /// the deposit for storage changed by the contracts it called in turn. The deposit for the code
+    /// uploaded along with a deployment is not a part of the contract's storage, so it is left
+    /// out. Interactions that did not move any deposit are skipped, but the contract still gets an
+    /// (empty) ledger.
+    pub fn storage_deposits(&self) -> BTreeMap<AccountIdFor<Config>, DepositLedger<Config>> {
+        let mut deploy_results = self.deploy_results.iter();
+        let mut call_results = self.call_results.iter();
+        let mut ledgers = BTreeMap::<_, DepositLedger<Config>>::new();
+
+        for entry in self.transcript.entries() {
+            let (context, address, deposit) = match entry {
+                TranscriptEntry::Deploy {
+                    context,
+                    uploaded,
+                    events,
+                    ..
+                } => {
+                    let Some(result) = deploy_results.next() else {
+                        break;
+                    };
+                    match &result.result {
+                        Ok(ret) if !ret.result.did_revert() => {
+                            // `pallet_contracts` adds the deposit for the uploaded code to the
+                            // storage deposit of the instantiation.
+                            let deposit =
+                                match uploaded {
+                                    true => result.storage_deposit.saturating_sub(
+                                        &StorageDeposit::Charge(code_deposit::<Config>(events)),
+                                    ),
+                                    false => result.storage_deposit.clone(),
+                                };
+                            (context, ret.account_id.clone(), deposit)
+                        }
+                        _ => continue,
+                    }
+                }
+                TranscriptEntry::Call {
+                    context, address, ..
+                } => {
+                    let Some(result) = call_results.next() else {
+                        break;
+                    };
+                    (context, address.clone(), result.storage_deposit.clone())
+                }
+                _ => continue,
+            };
+
+            let ledger = ledgers.entry(address).or_default();
+            if charge_of(&deposit).is_zero() && refund_of(&deposit).is_zero() {
+                continue;
+            }
+            ledger.movements.push(DepositMovement {
+                payer: context.actor.clone(),
+                deposit,
+            });
+        }
+
+        ledgers
+    }
+}
+
+/// Sums the deposits held for the code stored during an interaction (see
+/// `pallet_contracts::Event::CodeStored`), given its SCALE-encoded runtime events.
+fn code_deposit<Config: pallet_contracts::Config>(events: &[Vec<u8>]) -> BalanceOf<Config> {
+    let Some(index) =
+        <Config as frame_system::Config>::PalletInfo::index::<pallet_contracts::Pallet<Config>>()
+    else {
+        return Zero::zero();
+    };
+
+    events
+        .iter()
+        .filter_map(|encoded| {
+            let (&pallet, mut event) = encoded.split_first()?;
+            match pallet_contracts::Event::<Config>::decode(&mut event) {
+                Ok(pallet_contracts::Event::CodeStored { deposit_held, .. })
+                    if pallet as usize == index =>
+                {
+                    Some(deposit_held)
+                }
+                _ => None,
+            }
+        })
+        .fold(Zero::zero(), |total: BalanceOf<Config>, deposit| {
+            total.saturating_add(deposit)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use frame_support::weights::Weight;
+    use pallet_contracts::{
+        ContractResult, ExecReturnValue, InstantiateReturnValue, StorageDeposit,
+    };
+    use pallet_contracts_uapi::ReturnFlags;
+    use parity_scale_codec::Encode;
+
+    use crate::{
+        minimal::RuntimeEvent,
+        session::{
+            transcript::{InteractionContext, Outcome, TranscriptEntry},
+            Record,
+        },
+        AccountId32, MinimalRuntime, DEFAULT_GAS_LIMIT,
+    };
+
+    const CONTRACT: AccountId32 = AccountId32::new([7u8; 32]);
+    const ALICE: AccountId32 = AccountId32::new([1u8; 32]);
+    const BOB: AccountId32 = AccountId32::new([2u8; 32]);
+
+    fn record_call(
+        record: &mut Record<MinimalRuntime>,
+        actor: AccountId32,
+        deposit: StorageDeposit<u128>,
+    ) {
+        record.push_transcript_entry(TranscriptEntry::Call {
+            context: InteractionContext {
+                actor,
+                gas_limit: DEFAULT_GAS_LIMIT,
+            },
+            address: CONTRACT,
+            message: "message".to_string(),
+            args: vec![],
+            endowment: None,
+            outcome: Outcome::Success(vec![]),
+            events: vec![],
+        });
+        record.push_call_result(ContractResult {
+            gas_consumed: Weight::zero(),
+            gas_required: Weight::zero(),
+            storage_deposit: deposit,
+            debug_message: vec![],
+            result: Ok(ExecReturnValue {
+                flags: ReturnFlags::empty(),
+                data: vec![],
+            }),
+            events: None,
+        });
+    }
+
+    fn record_deploy_with_upload(
+        record: &mut Record<MinimalRuntime>,
+        code_deposit: u128,
+        deposit: StorageDeposit<u128>,
+    ) {
+        let code_stored = RuntimeEvent::Contracts(pallet_contracts::Event::CodeStored {
+            code_hash: Default::default(),
+            deposit_held: code_deposit,
+            uploader: ALICE,
+        });
+        record.push_transcript_entry(TranscriptEntry::Deploy {
+            context: InteractionContext {
+                actor: ALICE,
+                gas_limit: DEFAULT_GAS_LIMIT,
+            },
+            code_hash: Default::default(),
+            uploaded: true,
+            constructor: "new".to_string(),
+            args: vec![],
+            salt: vec![],
+            endowment: None,
+            outcome: Outcome::Success(CONTRACT.encode()),
+            events: vec![code_stored.encode()],
+        });
+        record.push_deploy_result(ContractResult {
+            gas_consumed: Weight::zero(),
+            gas_required: Weight::zero(),
+            storage_deposit: deposit,
+            debug_message: vec![],
+            result: Ok(InstantiateReturnValue {
+                result: ExecReturnValue {
+                    flags: ReturnFlags::empty(),
+                    data: vec![],
+                },
+                account_id: CONTRACT,
+            }),
+            events: None,
+        });
+    }
+
+    #[test]
+    fn code_upload_deposit_is_not_booked_to_contract() {
+        let mut record = Record::<MinimalRuntime>::default();
+        record_deploy_with_upload(&mut record, 30, StorageDeposit::Charge(100));
+
+        let ledgers = record.storage_deposits();
+        let ledger = &ledgers[&CONTRACT];
+        assert_eq!(ledger.charged(), 70);
+        assert_eq!(ledger.charged_to(&ALICE), 70);
+    }
+
+    #[test]
+    fn deposits_are_accounted_per_payer() {
+        let mut record = Record::<MinimalRuntime>::default();
+        record_call(&mut record, ALICE, StorageDeposit::Charge(100));
+        record_call(&mut record, BOB, StorageDeposit::Charge(50));
+        record_call(&mut record, ALICE, StorageDeposit::Charge(0));
+        record_call(&mut record, ALICE, StorageDeposit::Refund(80));
+
+        let ledgers = record.storage_deposits();
+        let ledger = &ledgers[&CONTRACT];
+
+        assert_eq!(ledger.movements().len(), 3);
+        assert_eq!(ledger.charged(), 150);
+        assert_eq!(ledger.refunded(), 80);
+        assert_eq!(ledger.charged_to(&ALICE), 100);
+        assert_eq!(ledger.refunded_to(&ALICE), 80);
+        assert_eq!(ledger.refunded_to(&BOB), 0);
+        assert_eq!(ledger.payers(), vec![ALICE, BOB]);
+    }
+}
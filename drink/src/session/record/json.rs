@@ -0,0 +1,200 @@
+//! JSON export of the session record, meant for archiving test runs and feeding external tools.
+
+use std::fmt::Write as _;
+
+use frame_support::sp_runtime::traits::UniqueSaturatedInto;
+use pallet_contracts::StorageDeposit;
+use parity_scale_codec::Encode;
+use serde_json::{json, Value as JsonValue};
+
+use super::{decode_event_of_contract, EventBatch, Record};
+use crate::{
+    contracts_api::decode_debug_buffer,
+    runtime::RuntimeEventOf,
+    session::transcript::{InteractionContext, TranscriptEntry},
+    Weight,
+};
+
+impl<Config: pallet_contracts::Config> Record<Config>
+where
+    RuntimeEventOf<Config>: TryInto<pallet_contracts::Event<Config>>,
+{
+    /// Serializes all the deployments and calls performed during the session to JSON.
+    ///
+    /// Interactions are listed in the order of execution. Each of them contains the actor, the
+    /// called contract, the constructor/message with its arguments, the outcome with the decoded
+    /// return value, gas consumption, storage deposit, debug messages and all the contract events
+    /// emitted meanwhile (decoded with the transcoder of the emitting contract, if known).
+    ///
+    /// Contracts and hashes are represented as hex-encoded SCALE bytes, balances as decimal
+    /// strings.
+    pub fn to_json(&self) -> JsonValue {
+        let mut deploy_results = self.deploy_results.iter();
+        let mut call_results = self.call_results.iter();
+        let mut event_batches = self.event_batches.iter();
+
+        let mut interactions = vec![];
+        for entry in self.transcript.entries() {
+            let interaction = match entry {
+                TranscriptEntry::Deploy {
+                    context,
+                    code_hash,
+                    uploaded,
+                    constructor,
+                    args,
+                    salt,
+                    endowment,
+                    ..
+                } => {
+                    let (Some(result), Some(events)) =
+                        (deploy_results.next(), event_batches.next())
+                    else {
+                        break;
+                    };
+
+                    let (outcome, address, error) = match &result.result {
+                        Ok(ret) if ret.result.did_revert() => ("reverted", None, None),
+                        Ok(ret) => ("success", Some(hex(&ret.account_id.encode())), None),
+                        Err(err) => ("failed", None, Some(format!("{err:?}"))),
+                    };
+
+                    json!({
+                        "kind": if *uploaded { "deploy" } else { "instantiate" },
+                        "context": context_json(context),
+                        "code_hash": hex(code_hash.as_ref()),
+                        "constructor": constructor,
+                        "args": args,
+                        "salt": hex(salt),
+                        "endowment": endowment.map(balance_json),
+                        "outcome": outcome,
+                        "address": address,
+                        "error": error,
+                        "gas_consumed": weight_json(result.gas_consumed),
+                        "gas_required": weight_json(result.gas_required),
+                        "storage_deposit": deposit_json(&result.storage_deposit),
+                        "debug_messages": decode_debug_buffer(&result.debug_message),
+                        "events": self.events_json(events),
+                    })
+                }
+                TranscriptEntry::Call {
+                    context,
+                    address,
+                    message,
+                    args,
+                    endowment,
+                    ..
+                } => {
+                    let (Some(result), Some(events)) = (call_results.next(), event_batches.next())
+                    else {
+                        break;
+                    };
+
+                    let (outcome, data, error) = match &result.result {
+                        Ok(ret) if ret.did_revert() => ("reverted", Some(&ret.data), None),
+                        Ok(ret) => ("success", Some(&ret.data), None),
+                        Err(err) => ("failed", None, Some(format!("{err:?}"))),
+                    };
+                    let decoded_return = data.and_then(|data| {
+                        self.decoding_transcoder(address)?
+                            .decode_message_return(message, &mut data.as_slice())
+                            .ok()
+                            .map(|value| value.to_string())
+                    });
+
+                    json!({
+                        "kind": "call",
+                        "context": context_json(context),
+                        "address": hex(&address.encode()),
+                        "message": message,
+                        "args": args,
+                        "endowment": endowment.map(balance_json),
+                        "outcome": outcome,
+                        "return_data": data.map(|data| hex(data)),
+                        "return_decoded": decoded_return,
+                        "error": error,
+                        "gas_consumed": weight_json(result.gas_consumed),
+                        "gas_required": weight_json(result.gas_required),
+                        "storage_deposit": deposit_json(&result.storage_deposit),
+                        "debug_messages": decode_debug_buffer(&result.debug_message),
+                        "events": self.events_json(events),
+                    })
+                }
+                _ => continue,
+            };
+            interactions.push(interaction);
+        }
+
+        json!({ "interactions": interactions })
+    }
+
+    fn events_json(&self, batch: &EventBatch<Config>) -> Vec<JsonValue> {
+        batch
+            .emitted_events()
+            .iter()
+            .map(|event| {
+                let decoded = self
+                    .decoding_transcoder(&event.contract)
+                    .and_then(|transcoder| decode_event_of_contract(&transcoder, event))
+                    .map(|value| value.to_string());
+                json!({
+                    "contract": hex(&event.contract.encode()),
+                    "data": hex(&event.data),
+                    "decoded": decoded,
+                })
+            })
+            .collect()
+    }
+}
+
+fn context_json<R: pallet_contracts::Config>(context: &InteractionContext<R>) -> JsonValue {
+    json!({
+        "actor": hex(&context.actor.encode()),
+        "gas_limit": weight_json(context.gas_limit),
+    })
+}
+
+fn weight_json(weight: Weight) -> JsonValue {
+    json!({
+        "ref_time": weight.ref_time(),
+        "proof_size": weight.proof_size(),
+    })
+}
+
+fn deposit_json<Balance: UniqueSaturatedInto<u128> + Copy>(
+    deposit: &StorageDeposit<Balance>,
+) -> JsonValue {
+    match deposit {
+        StorageDeposit::Charge(amount) => json!({ "charge": balance_json(*amount) }),
+        StorageDeposit::Refund(amount) => json!({ "refund": balance_json(*amount) }),
+    }
+}
+
+fn balance_json<Balance: UniqueSaturatedInto<u128>>(balance: Balance) -> String {
+    UniqueSaturatedInto::<u128>::unique_saturated_into(balance).to_string()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::from("0x"), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::hex;
+    use crate::{session::Record, MinimalRuntime};
+
+    #[test]
+    fn empty_record_serializes_to_no_interactions() {
+        let record = Record::<MinimalRuntime>::default();
+        assert_eq!(record.to_json(), json!({ "interactions": [] }));
+    }
+
+    #[test]
+    fn bytes_are_hex_encoded() {
+        assert_eq!(hex(&[0, 15, 255]), "0x000fff");
+    }
+}
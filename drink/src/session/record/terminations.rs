@@ -0,0 +1,181 @@
+//! Contracts terminated during the session.
+
+use frame_support::{sp_runtime::traits::Zero, CloneNoBound, DebugNoBound, PartialEqNoBound};
+
+use super::Record;
+use crate::{
+    runtime::{AccountIdFor, RuntimeEventOf},
+    BalanceOf,
+};
+
+/// A contract terminated (with `seal_terminate`) during the session.
+#[derive(CloneNoBound, DebugNoBound, PartialEqNoBound)]
+pub struct Termination<Config: pallet_contracts::Config + pallet_balances::Config> {
+    /// Address of the terminated contract.
+    pub contract: AccountIdFor<Config>,
+    /// The account that received the remaining balance of the contract.
+    pub beneficiary: AccountIdFor<Config>,
+    /// The balance transferred from the contract to the beneficiary.
+    pub refunded: BalanceOf<Config>,
+    /// Index of the event batch (i.e. of the interaction) during which the contract terminated.
+    pub batch: usize,
+}
+
+impl<Config> Record<Config>
+where
+    Config: pallet_contracts::Config + pallet_balances::Config,
+    RuntimeEventOf<Config>:
+        TryInto<pallet_contracts::Event<Config>> + TryInto<pallet_balances::Event<Config>>,
+{
+    /// Returns all the contracts terminated during the session, in the order of termination.
+    ///
+    /// The refunded amount is the last transfer from the contract to the beneficiary before the
+    /// `Terminated` event, i.e. the transfer of the remaining contract balance. Earlier transfers
+    /// between the same accounts within the interaction are not included. The storage deposit of
+    /// the contract is refunded to the origin separately (see `storage_deposits`).
+    pub fn terminations(&self) -> Vec<Termination<Config>> {
+        let mut terminations = vec![];
+
+        for (batch, events) in self.event_batches.iter().enumerate() {
+            let all_events = events.all_events();
+            for (index, record) in all_events.iter().enumerate() {
+                let Ok(pallet_contracts::Event::<Config>::Terminated {
+                    contract,
+                    beneficiary,
+                }) = TryInto::<pallet_contracts::Event<Config>>::try_into(record.event.clone())
+                else {
+                    continue;
+                };
+
+                let refunded = all_events[..index]
+                    .iter()
+                    .rev()
+                    .find_map(|record| {
+                        match TryInto::<pallet_balances::Event<Config>>::try_into(
+                            record.event.clone(),
+                        ) {
+                            Ok(pallet_balances::Event::Transfer { from, to, amount })
+                                if from == contract && to == beneficiary =>
+                            {
+                                Some(amount)
+                            }
+                            _ => None,
+                        }
+                    })
+                    .unwrap_or_else(Zero::zero);
+
+                terminations.push(Termination {
+                    contract,
+                    beneficiary,
+                    refunded,
+                    batch,
+                });
+            }
+        }
+
+        terminations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::tests::record_of, Termination};
+    use crate::{
+        runtime::{minimal::RuntimeEvent, MinimalRuntime},
+        session::Record,
+        AccountId32, EventRecordOf,
+    };
+
+    const CONTRACT: AccountId32 = AccountId32::new([7u8; 32]);
+    const BENEFICIARY: AccountId32 = AccountId32::new([8u8; 32]);
+
+    fn transfer(from: AccountId32, to: AccountId32, amount: u128) -> EventRecordOf<MinimalRuntime> {
+        record_of(RuntimeEvent::Balances(pallet_balances::Event::Transfer {
+            from,
+            to,
+            amount,
+        }))
+    }
+
+    #[test]
+    fn terminations_are_extracted_from_events() {
+        let mut record = Record::<MinimalRuntime>::default();
+        record.push_event_batches(vec![], vec![]);
+        record.push_event_batches(
+            vec![
+                transfer(CONTRACT, BENEFICIARY, 30),
+                transfer(BENEFICIARY, CONTRACT, 5),
+                record_of(RuntimeEvent::Contracts(
+                    pallet_contracts::Event::Terminated {
+                        contract: CONTRACT,
+                        beneficiary: BENEFICIARY,
+                    },
+                )),
+            ],
+            vec![],
+        );
+
+        assert_eq!(
+            record.terminations(),
+            vec![Termination {
+                contract: CONTRACT,
+                beneficiary: BENEFICIARY,
+                refunded: 30,
+                batch: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn only_the_final_transfer_is_refunded() {
+        let mut record = Record::<MinimalRuntime>::default();
+        record.push_event_batches(
+            vec![
+                // A regular transfer to the beneficiary, made by the contract before terminating.
+                transfer(CONTRACT, BENEFICIARY, 10),
+                transfer(CONTRACT, BENEFICIARY, 30),
+                record_of(RuntimeEvent::Contracts(
+                    pallet_contracts::Event::Terminated {
+                        contract: CONTRACT,
+                        beneficiary: BENEFICIARY,
+                    },
+                )),
+                // A transfer after the termination doesn't belong to it either.
+                transfer(CONTRACT, BENEFICIARY, 50),
+            ],
+            vec![],
+        );
+
+        assert_eq!(
+            record.terminations(),
+            vec![Termination {
+                contract: CONTRACT,
+                beneficiary: BENEFICIARY,
+                refunded: 30,
+                batch: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn only_terminated_contracts_are_found() {
+        let mut record = Record::<MinimalRuntime>::default();
+        record.push_event_batches(
+            vec![
+                transfer(CONTRACT, BENEFICIARY, 30),
+                record_of(RuntimeEvent::Contracts(
+                    pallet_contracts::Event::Terminated {
+                        contract: CONTRACT,
+                        beneficiary: BENEFICIARY,
+                    },
+                )),
+            ],
+            vec![],
+        );
+
+        assert_eq!(
+            record.last_event_batch().terminated_contracts(),
+            vec![CONTRACT]
+        );
+    }
+}
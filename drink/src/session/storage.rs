@@ -0,0 +1,114 @@
+//! Decoding of contract storage with the storage layout from the contract metadata.
+
+use contract_transcode::{ContractMessageTranscoder, Value};
+use ink_metadata::layout::{Layout, RootLayout};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::form::PortableForm;
+
+/// Decoded content of a single storage cell of a contract.
+#[derive(Clone, Debug)]
+pub struct StorageCell {
+    /// The root key of the cell, as declared in the storage layout.
+    pub root_key: u32,
+    /// Decoded values of all the fields stored in the cell, in the layout order.
+    pub values: Vec<Value>,
+}
+
+/// Decodes all the root storage cells of a contract (the contract struct and `Lazy` fields)
+/// according to the layout from the metadata of `transcoder`.
+///
+/// `read` returns the raw value stored under the given key. Cells that are not set (like the
+/// cells of `Mapping`s, whose entries live under derived keys) are skipped.
+pub(crate) fn decode_storage(
+    transcoder: &ContractMessageTranscoder,
+    mut read: impl FnMut(Vec<u8>) -> Result<Option<Vec<u8>>, String>,
+) -> Result<Vec<StorageCell>, String> {
+    let mut roots = vec![];
+    collect_roots(transcoder.metadata().layout(), &mut roots);
+
+    let mut cells = vec![];
+    for root in roots {
+        let root_key = *root.root_key().key();
+        let Some(raw) = read(root_key.encode())? else {
+            continue;
+        };
+
+        let mut input = &raw[..];
+        let mut values = vec![];
+        decode_cell(transcoder, root.layout(), &mut input, &mut values)
+            .map_err(|err| format!("Cell {root_key:#010x}: {err}"))?;
+        if !input.is_empty() {
+            return Err(format!(
+                "Cell {root_key:#010x}: {} trailing bytes after decoding",
+                input.len()
+            ));
+        }
+        cells.push(StorageCell { root_key, values });
+    }
+    Ok(cells)
+}
+
+/// Collects all the root layouts nested in `layout` (including `layout` itself).
+pub(crate) fn collect_roots<'a>(
+    layout: &'a Layout<PortableForm>,
+    roots: &mut Vec<&'a RootLayout<PortableForm>>,
+) {
+    match layout {
+        Layout::Root(root) => {
+            roots.push(root);
+            collect_roots(root.layout(), roots);
+        }
+        Layout::Struct(layout) => layout
+            .fields()
+            .iter()
+            .for_each(|field| collect_roots(field.layout(), roots)),
+        Layout::Enum(layout) => layout.variants().values().for_each(|variant| {
+            variant
+                .fields()
+                .iter()
+                .for_each(|field| collect_roots(field.layout(), roots))
+        }),
+        Layout::Array(layout) => collect_roots(layout.layout(), roots),
+        Layout::Hash(layout) => collect_roots(layout.layout(), roots),
+        Layout::Leaf(_) => {}
+    }
+}
+
+/// Decodes the values stored directly in the cell described by `layout`, skipping nested cells.
+fn decode_cell(
+    transcoder: &ContractMessageTranscoder,
+    layout: &Layout<PortableForm>,
+    input: &mut &[u8],
+    values: &mut Vec<Value>,
+) -> Result<(), String> {
+    match layout {
+        Layout::Leaf(leaf) => values.push(
+            transcoder
+                .decode(leaf.ty().id, input)
+                .map_err(|err| err.to_string())?,
+        ),
+        Layout::Struct(layout) => {
+            for field in layout.fields() {
+                decode_cell(transcoder, field.layout(), input, values)?;
+            }
+        }
+        Layout::Enum(layout) => {
+            let discriminant = u8::decode(input).map_err(|err| err.to_string())?;
+            let (_, variant) = layout
+                .variants()
+                .iter()
+                .find(|(candidate, _)| candidate.value() == discriminant as usize)
+                .ok_or_else(|| format!("Unknown variant {discriminant} of `{}`", layout.name()))?;
+            for field in variant.fields() {
+                decode_cell(transcoder, field.layout(), input, values)?;
+            }
+        }
+        Layout::Array(layout) => {
+            for _ in 0..layout.len() {
+                decode_cell(transcoder, layout.layout(), input, values)?;
+            }
+        }
+        Layout::Root(_) | Layout::Hash(_) => {}
+    }
+    Ok(())
+}
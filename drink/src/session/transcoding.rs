@@ -20,4 +20,14 @@ impl<Contract: Ord> TranscoderRegistry<Contract> {
     pub fn get(&self, contract: &Contract) -> Option<Rc<ContractMessageTranscoder>> {
         self.transcoders.get(contract).map(Rc::clone)
     }
+
+    pub fn remove(&mut self, contract: &Contract) -> Option<Rc<ContractMessageTranscoder>> {
+        self.transcoders.remove(contract)
+    }
+}
+
+impl<Contract: Ord> Default for TranscoderRegistry<Contract> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
@@ -0,0 +1,674 @@
+//! Module providing replayable transcripts of session interactions.
+//!
+//! A transcript is a compact, serializable log of everything that a `Session` was asked to do:
+//! uploads, deployments, calls, block and time advances and mock registrations, together with the context
+//! in which they were executed (actor, gas limit) and their observed outcomes. Replaying a
+//! transcript against a fresh session (possibly with newer versions of the contracts) reports
+//! every place where the results diverge.
+
+use std::{collections::BTreeMap, path::Path};
+
+use frame_support::{
+    sp_runtime::{traits::Hash, DispatchError},
+    traits::PalletInfo,
+    CloneNoBound, DebugNoBound, DefaultNoBound, PartialEqNoBound,
+};
+use parity_scale_codec::{Decode, DecodeAll, Encode};
+
+use crate::{
+    bundle::ContractBundle,
+    runtime::{AccountIdFor, HashFor},
+    session::{
+        error::SessionError,
+        record::{ContractExecResult, ContractInstantiateResult},
+        BalanceOf,
+    },
+    ContractMock, EventRecordOf, Weight,
+};
+
+/// A serializable log of the interactions performed within a session.
+///
+/// Transcripts are SCALE-encoded when saved to a file.
+#[derive(Encode, Decode, CloneNoBound, DebugNoBound, DefaultNoBound, PartialEqNoBound)]
+pub struct Transcript<R: pallet_contracts::Config> {
+    entries: Vec<TranscriptEntry<R>>,
+}
+
+impl<R: pallet_contracts::Config> Transcript<R> {
+    pub(super) fn push(&mut self, entry: TranscriptEntry<R>) {
+        self.entries.push(entry);
+    }
+
+    /// Returns all the recorded entries, in the order of execution.
+    pub fn entries(&self) -> &[TranscriptEntry<R>] {
+        &self.entries
+    }
+
+    /// Saves the SCALE-encoded transcript under `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SessionError> {
+        std::fs::write(path, self.encode())
+            .map_err(|err| SessionError::Transcript(format!("Failed to save transcript: {err}")))
+    }
+
+    /// Loads a SCALE-encoded transcript from `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SessionError> {
+        let bytes = std::fs::read(path)
+            .map_err(|err| SessionError::Transcript(format!("Failed to read transcript: {err}")))?;
+        Self::decode(&mut &bytes[..]).map_err(|err| {
+            SessionError::Transcript(format!("Failed to decode transcript: {err:?}"))
+        })
+    }
+}
+
+/// Context in which a contract interaction was executed.
+#[derive(Encode, Decode, CloneNoBound, DebugNoBound, PartialEqNoBound)]
+pub struct InteractionContext<R: pallet_contracts::Config> {
+    /// The account that performed the interaction.
+    pub actor: AccountIdFor<R>,
+    /// The gas limit used for the interaction.
+    pub gas_limit: Weight,
+}
+
+/// A single interaction recorded in a transcript.
+#[derive(Encode, Decode, CloneNoBound, DebugNoBound, PartialEqNoBound)]
+pub enum TranscriptEntry<R: pallet_contracts::Config> {
+    /// Code upload.
+    Upload {
+        /// The account that uploaded the code.
+        actor: AccountIdFor<R>,
+        /// Hash of the uploaded code.
+        code_hash: HashFor<R>,
+        /// The observed outcome. The return value is the encoded code hash.
+        outcome: Outcome,
+    },
+    /// Contract deployment.
+    Deploy {
+        /// Context of the deployment.
+        context: InteractionContext<R>,
+        /// Hash of the deployed code.
+        code_hash: HashFor<R>,
+        /// Whether the code was uploaded along with the deployment. Otherwise, an already uploaded
+        /// code was instantiated.
+        uploaded: bool,
+        /// Constructor label.
+        constructor: String,
+        /// Constructor arguments, as passed to the session (account references, like `@alice`,
+        /// are kept as they are, not resolved to addresses).
+        args: Vec<String>,
+        /// Salt used for the address derivation.
+        salt: Vec<u8>,
+        /// Endowment transferred to the contract.
+        endowment: Option<BalanceOf<R>>,
+        /// The observed outcome. The return value is the encoded address of the new contract.
+        outcome: Outcome,
+        /// SCALE-encoded runtime events emitted during the deployment.
+        events: Vec<Vec<u8>>,
+    },
+    /// Contract call.
+    Call {
+        /// Context of the call.
+        context: InteractionContext<R>,
+        /// Address of the called contract.
+        address: AccountIdFor<R>,
+        /// Message label.
+        message: String,
+        /// Message arguments, as passed to the session (account references, like `@alice`, are
+        /// kept as they are, not resolved to addresses).
+        args: Vec<String>,
+        /// Value transferred to the contract.
+        endowment: Option<BalanceOf<R>>,
+        /// The observed outcome. The return value is the raw data returned by the contract.
+        outcome: Outcome,
+        /// SCALE-encoded runtime events emitted during the call.
+        events: Vec<Vec<u8>>,
+    },
+    /// Advancing the chain by `count` empty blocks.
+    BuildBlocks {
+        /// Number of blocks built.
+        count: u32,
+    },
+    /// Building a block with the clock moved forward.
+    WarpTime {
+        /// How much time passed, in milliseconds.
+        millis: u64,
+    },
+    /// Deployment of a contract mock.
+    DeployMock {
+        /// Address of the mock.
+        address: AccountIdFor<R>,
+    },
+    /// Removal of an uploaded code.
+    RemoveCode {
+        /// The account that removed the code.
+        actor: AccountIdFor<R>,
+        /// Hash of the removed code.
+        code_hash: HashFor<R>,
+        /// The observed outcome.
+        outcome: Outcome,
+    },
+    /// Replacing the code of a contract by root.
+    SetCode {
+        /// Address of the upgraded contract.
+        address: AccountIdFor<R>,
+        /// Hash of the new code.
+        code_hash: HashFor<R>,
+        /// The observed outcome.
+        outcome: Outcome,
+    },
+    /// Minting tokens to an account.
+    Mint {
+        /// The funded account.
+        recipient: AccountIdFor<R>,
+        /// Number of minted tokens.
+        amount: BalanceOf<R>,
+    },
+}
+
+impl<R: pallet_contracts::Config> TranscriptEntry<R> {
+    /// Returns the observed outcome and events, if the entry has any.
+    pub(super) fn observations(&self) -> Option<(Outcome, Vec<Vec<u8>>)> {
+        match self {
+            Self::Upload { outcome, .. }
+            | Self::RemoveCode { outcome, .. }
+            | Self::SetCode { outcome, .. } => Some((outcome.clone(), vec![])),
+            Self::Deploy {
+                outcome, events, ..
+            }
+            | Self::Call {
+                outcome, events, ..
+            } => Some((outcome.clone(), events.clone())),
+            Self::BuildBlocks { .. }
+            | Self::WarpTime { .. }
+            | Self::DeployMock { .. }
+            | Self::Mint { .. } => None,
+        }
+    }
+}
+
+/// Outcome of a recorded interaction.
+#[derive(Encode, Decode, Clone, Debug, PartialEq)]
+pub enum Outcome {
+    /// The interaction succeeded with the given return value.
+    Success(Vec<u8>),
+    /// The contract reverted with the given return value.
+    Reverted(Vec<u8>),
+    /// The interaction was aborted by the pallet.
+    Failed(DispatchError),
+}
+
+impl Outcome {
+    pub(super) fn of_deploy<R: pallet_contracts::Config>(
+        result: &ContractInstantiateResult<R>,
+    ) -> Self {
+        match &result.result {
+            Ok(exec_result) if exec_result.result.did_revert() => {
+                Self::Reverted(exec_result.result.data.clone())
+            }
+            Ok(exec_result) => Self::Success(exec_result.account_id.encode()),
+            Err(err) => Self::Failed(*err),
+        }
+    }
+
+    pub(super) fn of_call<R: pallet_contracts::Config>(result: &ContractExecResult<R>) -> Self {
+        match &result.result {
+            Ok(exec_result) if exec_result.did_revert() => Self::Reverted(exec_result.data.clone()),
+            Ok(exec_result) => Self::Success(exec_result.data.clone()),
+            Err(err) => Self::Failed(*err),
+        }
+    }
+}
+
+/// Encodes runtime events for storing them in a transcript.
+pub(super) fn encode_events<R: frame_system::Config>(events: &[EventRecordOf<R>]) -> Vec<Vec<u8>> {
+    events.iter().map(|record| record.event.encode()).collect()
+}
+
+/// Computes the hash under which `pallet-contracts` stores `code`.
+pub(super) fn code_hash<R: frame_system::Config>(code: &[u8]) -> HashFor<R> {
+    <R as frame_system::Config>::Hashing::hash(code)
+}
+
+/// Contracts and mocks needed to replay a transcript.
+///
+/// Bundles are looked up by the code hash recorded in the transcript. By default, a bundle is
+/// registered under its own code hash, but it can also substitute a different (e.g. older)
+/// version of the code with `with_bundle_for`. Mocks cannot be serialized, so they have to be
+/// provided again, in the same order in which they were deployed in the original session.
+pub struct ReplaySources<R: frame_system::Config> {
+    bundles: BTreeMap<HashFor<R>, ContractBundle>,
+    mocks: Vec<ContractMock>,
+}
+
+impl<R: frame_system::Config> ReplaySources<R> {
+    /// Creates an empty set of sources.
+    pub fn new() -> Self {
+        Self {
+            bundles: BTreeMap::new(),
+            mocks: Vec::new(),
+        }
+    }
+
+    /// Registers `bundle` under its own code hash.
+    pub fn with_bundle(self, bundle: ContractBundle) -> Self {
+        let hash = code_hash::<R>(&bundle.wasm);
+        self.with_bundle_for(hash, bundle)
+    }
+
+    /// Registers `bundle` as a replacement for the code with `recorded_hash`.
+    pub fn with_bundle_for(mut self, recorded_hash: HashFor<R>, bundle: ContractBundle) -> Self {
+        self.bundles.insert(recorded_hash, bundle);
+        self
+    }
+
+    /// Adds the next mock to be deployed.
+    pub fn with_mock(mut self, mock: ContractMock) -> Self {
+        self.mocks.push(mock);
+        self
+    }
+
+    pub(super) fn bundle(
+        &self,
+        recorded_hash: &HashFor<R>,
+    ) -> Result<ContractBundle, SessionError> {
+        self.bundles.get(recorded_hash).cloned().ok_or_else(|| {
+            SessionError::Transcript(format!("Missing bundle for code hash {recorded_hash:?}"))
+        })
+    }
+
+    pub(super) fn next_mock(&mut self) -> Result<ContractMock, SessionError> {
+        if self.mocks.is_empty() {
+            return Err(SessionError::Transcript(
+                "Missing mock for a recorded mock deployment".to_string(),
+            ));
+        }
+        Ok(self.mocks.remove(0))
+    }
+}
+
+impl<R: frame_system::Config> Default for ReplaySources<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Translation between the addresses and code hashes of the original session and the replaying
+/// one.
+///
+/// When a contract is replayed with a different version of its code, both its code hash and its
+/// address change. To compare results, we map the new identifiers back to the recorded ones in the
+/// typed fields of the observations: the address returned by a deployment, the code hash returned
+/// by an upload and the account and code hash fields of the `System`, `Balances` and `Contracts`
+/// events. Opaque data, like call return values or contract event payloads, is compared as it is.
+pub(super) struct IdentifierMap<R: pallet_contracts::Config + pallet_balances::Config> {
+    /// Recorded address -> replayed address.
+    replayed_accounts: BTreeMap<AccountIdFor<R>, AccountIdFor<R>>,
+    /// Replayed address -> recorded address.
+    recorded_accounts: BTreeMap<AccountIdFor<R>, AccountIdFor<R>>,
+    /// Replayed code hash -> recorded code hash.
+    recorded_code_hashes: BTreeMap<HashFor<R>, HashFor<R>>,
+}
+
+impl<R: pallet_contracts::Config + pallet_balances::Config> IdentifierMap<R> {
+    pub fn new() -> Self {
+        Self {
+            replayed_accounts: BTreeMap::new(),
+            recorded_accounts: BTreeMap::new(),
+            recorded_code_hashes: BTreeMap::new(),
+        }
+    }
+
+    /// Notes that the account `replayed` corresponds to the recorded account `recorded`.
+    pub fn insert_account(&mut self, recorded: AccountIdFor<R>, replayed: AccountIdFor<R>) {
+        self.replayed_accounts
+            .insert(recorded.clone(), replayed.clone());
+        self.recorded_accounts.insert(replayed, recorded);
+    }
+
+    /// Notes that the code hash `replayed` corresponds to the recorded code hash `recorded`.
+    pub fn insert_code_hash(&mut self, recorded: HashFor<R>, replayed: HashFor<R>) {
+        self.recorded_code_hashes.insert(replayed, recorded);
+    }
+
+    /// Returns the replayed counterpart of the recorded account `recorded`.
+    pub fn replayed_account(&self, recorded: &AccountIdFor<R>) -> AccountIdFor<R> {
+        self.replayed_accounts
+            .get(recorded)
+            .cloned()
+            .unwrap_or_else(|| recorded.clone())
+    }
+
+    /// Returns the observations of the replayed `entry`, with all the known replayed identifiers
+    /// mapped back to the recorded ones.
+    pub fn normalized_observations(
+        &self,
+        entry: &TranscriptEntry<R>,
+    ) -> Option<(Outcome, Vec<Vec<u8>>)> {
+        let (outcome, events) = entry.observations()?;
+        let outcome = match (entry, outcome) {
+            (TranscriptEntry::Upload { .. }, Outcome::Success(code_hash)) => {
+                Outcome::Success(map_encoded(code_hash, |code_hash| {
+                    self.map_code_hash(code_hash)
+                }))
+            }
+            (TranscriptEntry::Deploy { .. }, Outcome::Success(address)) => {
+                Outcome::Success(map_encoded(address, |address| self.map_account(address)))
+            }
+            (_, outcome) => outcome,
+        };
+        let events = events
+            .into_iter()
+            .map(|event| self.normalize_event(event))
+            .collect();
+        Some((outcome, events))
+    }
+
+    /// Maps the identifiers in a SCALE-encoded runtime event. Events of other pallets than
+    /// `System`, `Balances` and `Contracts` are returned as they are.
+    fn normalize_event(&self, event: Vec<u8>) -> Vec<u8> {
+        let Some((&pallet, _)) = event.split_first() else {
+            return event;
+        };
+        let pallet = Some(pallet as usize);
+        if pallet == <R as frame_system::Config>::PalletInfo::index::<frame_system::Pallet<R>>() {
+            map_pallet_event(event, |event| self.map_system_event(event))
+        } else if pallet
+            == <R as frame_system::Config>::PalletInfo::index::<pallet_balances::Pallet<R>>()
+        {
+            map_pallet_event(event, |event| self.map_balances_event(event))
+        } else if pallet
+            == <R as frame_system::Config>::PalletInfo::index::<pallet_contracts::Pallet<R>>()
+        {
+            map_pallet_event(event, |event| self.map_contracts_event(event))
+        } else {
+            event
+        }
+    }
+
+    fn map_account(&self, mut account: AccountIdFor<R>) -> AccountIdFor<R> {
+        self.map_account_in_place(&mut account);
+        account
+    }
+
+    fn map_account_in_place(&self, account: &mut AccountIdFor<R>) {
+        if let Some(recorded) = self.recorded_accounts.get(account) {
+            *account = recorded.clone();
+        }
+    }
+
+    fn map_code_hash(&self, mut code_hash: HashFor<R>) -> HashFor<R> {
+        self.map_code_hash_in_place(&mut code_hash);
+        code_hash
+    }
+
+    fn map_code_hash_in_place(&self, code_hash: &mut HashFor<R>) {
+        if let Some(recorded) = self.recorded_code_hashes.get(code_hash) {
+            *code_hash = *recorded;
+        }
+    }
+
+    fn map_system_event(&self, mut event: frame_system::Event<R>) -> frame_system::Event<R> {
+        match &mut event {
+            frame_system::Event::NewAccount { account }
+            | frame_system::Event::KilledAccount { account } => self.map_account_in_place(account),
+            _ => {}
+        }
+        event
+    }
+
+    fn map_balances_event(
+        &self,
+        mut event: pallet_balances::Event<R>,
+    ) -> pallet_balances::Event<R> {
+        use pallet_balances::Event;
+
+        match &mut event {
+            Event::Endowed { account, .. } | Event::DustLost { account, .. } => {
+                self.map_account_in_place(account)
+            }
+            Event::Transfer { from, to, .. } | Event::ReserveRepatriated { from, to, .. } => {
+                self.map_account_in_place(from);
+                self.map_account_in_place(to);
+            }
+            Event::BalanceSet { who, .. }
+            | Event::Reserved { who, .. }
+            | Event::Unreserved { who, .. }
+            | Event::Deposit { who, .. }
+            | Event::Withdraw { who, .. }
+            | Event::Slashed { who, .. }
+            | Event::Minted { who, .. }
+            | Event::Burned { who, .. } => self.map_account_in_place(who),
+            _ => {}
+        }
+        event
+    }
+
+    fn map_contracts_event(
+        &self,
+        mut event: pallet_contracts::Event<R>,
+    ) -> pallet_contracts::Event<R> {
+        use pallet_contracts::Event;
+
+        match &mut event {
+            Event::Instantiated { deployer, contract } => {
+                self.map_account_in_place(deployer);
+                self.map_account_in_place(contract);
+            }
+            Event::Terminated {
+                contract,
+                beneficiary,
+            } => {
+                self.map_account_in_place(contract);
+                self.map_account_in_place(beneficiary);
+            }
+            Event::CodeStored { code_hash, .. } | Event::CodeRemoved { code_hash, .. } => {
+                self.map_code_hash_in_place(code_hash)
+            }
+            Event::ContractEmitted { contract, .. } => self.map_account_in_place(contract),
+            Event::ContractCodeUpdated {
+                contract,
+                new_code_hash,
+                old_code_hash,
+            } => {
+                self.map_account_in_place(contract);
+                self.map_code_hash_in_place(new_code_hash);
+                self.map_code_hash_in_place(old_code_hash);
+            }
+            Event::Called { caller, contract } => {
+                if let pallet_contracts::Origin::Signed(caller) = caller {
+                    self.map_account_in_place(caller);
+                }
+                self.map_account_in_place(contract);
+            }
+            Event::DelegateCalled {
+                contract,
+                code_hash,
+            } => {
+                self.map_account_in_place(contract);
+                self.map_code_hash_in_place(code_hash);
+            }
+            Event::StorageDepositTransferredAndHeld { from, to, .. }
+            | Event::StorageDepositTransferredAndReleased { from, to, .. } => {
+                self.map_account_in_place(from);
+                self.map_account_in_place(to);
+            }
+            _ => {}
+        }
+        event
+    }
+}
+
+/// Decodes `data` as `T`, applies `map` and encodes the result back. Data that doesn't decode as
+/// `T` is returned as it is.
+fn map_encoded<T: Encode + Decode>(data: Vec<u8>, map: impl FnOnce(T) -> T) -> Vec<u8> {
+    match T::decode_all(&mut &data[..]) {
+        Ok(value) => map(value).encode(),
+        Err(_) => data,
+    }
+}
+
+/// Like `map_encoded`, but for a runtime event, which is prefixed with the index of its pallet.
+fn map_pallet_event<T: Encode + Decode>(event: Vec<u8>, map: impl FnOnce(T) -> T) -> Vec<u8> {
+    match event.split_first() {
+        Some((&pallet, data)) => [vec![pallet], map_encoded(data.to_vec(), map)].concat(),
+        None => event,
+    }
+}
+
+/// The result of replaying a transcript.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReplayReport {
+    divergences: Vec<Divergence>,
+}
+
+impl ReplayReport {
+    pub(super) fn push(&mut self, entry: usize, kind: DivergenceKind) {
+        self.divergences.push(Divergence { entry, kind });
+    }
+
+    /// Returns `true` if the replay reproduced the transcript exactly.
+    pub fn is_consistent(&self) -> bool {
+        self.divergences.is_empty()
+    }
+
+    /// Returns all the detected divergences, in the order of transcript entries.
+    pub fn divergences(&self) -> &[Divergence] {
+        &self.divergences
+    }
+}
+
+/// A difference between the recorded and the replayed behavior.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence {
+    /// Index of the transcript entry that diverged.
+    pub entry: usize,
+    /// What exactly diverged.
+    pub kind: DivergenceKind,
+}
+
+/// The kind of a divergence.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DivergenceKind {
+    /// The interaction had a different outcome.
+    Outcome {
+        /// Recorded outcome.
+        expected: Outcome,
+        /// Replayed outcome.
+        actual: Outcome,
+    },
+    /// The interaction emitted different events.
+    Events {
+        /// Recorded (encoded) events.
+        expected: Vec<Vec<u8>>,
+        /// Replayed (encoded) events.
+        actual: Vec<Vec<u8>>,
+    },
+    /// The interaction could not be replayed at all (e.g. the message no longer exists).
+    NotExecuted(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use parity_scale_codec::{Decode, Encode};
+
+    use super::{
+        IdentifierMap, InteractionContext, Outcome, ReplaySources, Transcript, TranscriptEntry,
+    };
+    use crate::{minimal::RuntimeEvent, session::Session, AccountId32, MinimalRuntime, Weight};
+
+    #[test]
+    fn identifiers_are_translated_back() {
+        let deployer = AccountId32::new([0u8; 32]);
+        let recorded = AccountId32::new([1u8; 32]);
+        let replayed = AccountId32::new([2u8; 32]);
+
+        let mut identifiers = IdentifierMap::<MinimalRuntime>::new();
+        identifiers.insert_account(recorded.clone(), replayed.clone());
+        assert_eq!(identifiers.replayed_account(&recorded), replayed);
+
+        let instantiated = |contract: &AccountId32| {
+            RuntimeEvent::Contracts(pallet_contracts::Event::Instantiated {
+                deployer: deployer.clone(),
+                contract: contract.clone(),
+            })
+            .encode()
+        };
+        // Contract event payloads are opaque, so they are not rewritten even if they happen to
+        // contain the bytes of a replayed address.
+        let emitted = RuntimeEvent::Contracts(pallet_contracts::Event::ContractEmitted {
+            contract: replayed.clone(),
+            data: replayed.encode(),
+        })
+        .encode();
+        let emitted_recorded = RuntimeEvent::Contracts(pallet_contracts::Event::ContractEmitted {
+            contract: recorded.clone(),
+            data: replayed.encode(),
+        })
+        .encode();
+
+        let entry = TranscriptEntry::<MinimalRuntime>::Deploy {
+            context: InteractionContext {
+                actor: deployer.clone(),
+                gas_limit: Weight::zero(),
+            },
+            code_hash: Default::default(),
+            uploaded: true,
+            constructor: "new".to_string(),
+            args: vec![],
+            salt: vec![],
+            endowment: None,
+            outcome: Outcome::Success(replayed.encode()),
+            events: vec![instantiated(&replayed), emitted],
+        };
+
+        assert_eq!(
+            identifiers.normalized_observations(&entry),
+            Some((
+                Outcome::Success(recorded.encode()),
+                vec![instantiated(&recorded), emitted_recorded]
+            ))
+        );
+    }
+
+    #[test]
+    fn transcript_encoding_roundtrips() {
+        let mut transcript = Transcript::<MinimalRuntime>::default();
+        transcript.push(TranscriptEntry::BuildBlocks { count: 3 });
+        transcript.push(TranscriptEntry::DeployMock {
+            address: AccountId32::new([3u8; 32]),
+        });
+        transcript.push(TranscriptEntry::Mint {
+            recipient: AccountId32::new([4u8; 32]),
+            amount: 1_000,
+        });
+
+        let decoded = Transcript::<MinimalRuntime>::decode(&mut &transcript.encode()[..])
+            .expect("Transcript should be decodable");
+        assert_eq!(decoded, transcript);
+    }
+
+    #[test]
+    fn minting_is_replayed() {
+        let recipient = AccountId32::new([4u8; 32]);
+
+        let mut session = Session::<MinimalRuntime>::new().unwrap();
+        session.mint_into(recipient.clone(), 1_000).unwrap();
+        let transcript = session.record().transcript().clone();
+        assert_eq!(
+            transcript.entries().last(),
+            Some(&TranscriptEntry::Mint {
+                recipient: recipient.clone(),
+                amount: 1_000,
+            })
+        );
+
+        let mut replayed = Session::<MinimalRuntime>::new().unwrap();
+        let report = replayed
+            .replay(&transcript, ReplaySources::new())
+            .expect("Replay should succeed");
+        assert!(report.is_consistent());
+        assert_eq!(
+            replayed.sandbox().free_balance(&recipient),
+            session.sandbox().free_balance(&recipient)
+        );
+    }
+}
@@ -43,7 +43,9 @@ type SynResult<T> = Result<T, syn::Error>;
 /// `drink::runtime::SandboxConfig` trait. Thus, your testcase function should accept a single argument:
 /// `mut session: Session<_>`.
 ///
-/// By default, the macro will use `drink::runtime::MinimalRuntime`.
+/// By default, the macro will use `drink::runtime::MinimalRuntime`. Runtimes resembling well-known
+/// contract chains are available in `drink::runtime::presets`, e.g.
+/// `#[drink::test(config = drink::runtime::presets::AstarLike)]`.
 ///
 /// # Example
 ///
@@ -36,20 +36,24 @@ mod contract_calling_chain_extension {
 #[cfg(test)]
 mod tests {
     use drink::{
-        create_minimal_runtime,
+        runtime::chain_extension::chain_extension_handler,
         session::{Session, NO_ARGS, NO_ENDOWMENT, NO_SALT},
     };
 
+    use self::{with_ce::SandboxWithCE, with_registry::SandboxWithRegistry};
     use crate::CHAIN_EXTENSION_RETURN_VALUE;
 
     #[drink::contract_bundle_provider]
     enum BundleProvider {}
 
-    // We can inject arbitrary chain extension into the minimal runtime as follows:
-    create_minimal_runtime!(
-        SandboxWithCE,
-        crate::chain_extension_runtime_side::StakingExtension
-    );
+    // We can inject arbitrary chain extension into the minimal runtime as follows (every runtime
+    // has to be created in a separate module):
+    mod with_ce {
+        drink::create_minimal_runtime!(
+            SandboxWithCE,
+            crate::chain_extension_runtime_side::StakingExtension
+        );
+    }
 
     /// Test that we can call chain extension from ink! contract and get a correct result.
     #[drink::test(config = SandboxWithCE)]
@@ -68,4 +72,45 @@ mod tests {
 
         Ok(())
     }
+
+    // Alternatively, we can use a single runtime with `ChainExtensionRegistry` and let every test
+    // register its own handlers on the session.
+    mod with_registry {
+        drink::create_minimal_runtime!(
+            SandboxWithRegistry,
+            drink::runtime::chain_extension::ChainExtensionRegistry
+        );
+    }
+
+    /// Test that the value returned by the chain extension can be chosen per session.
+    #[drink::test(config = SandboxWithRegistry)]
+    fn we_can_mock_chain_extension_per_session(
+        mut session: Session,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        session = session
+            .with_chain_extension_handler(0, 41, chain_extension_handler(|()| 7u32))
+            .deploy_bundle_and(
+                BundleProvider::local()?,
+                "new",
+                NO_ARGS,
+                NO_SALT,
+                NO_ENDOWMENT,
+            )?;
+
+        let result: u32 = session.call("call_ce", NO_ARGS, NO_ENDOWMENT)??;
+        assert_eq!(result, 7);
+
+        // Handlers can be swapped between calls.
+        session.set_chain_extension_handler(0, 41, chain_extension_handler(|()| 42u32));
+        let result: u32 = session.call("call_ce", NO_ARGS, NO_ENDOWMENT)??;
+        assert_eq!(result, 42);
+
+        // Without a handler, the contract call is trapped.
+        session.remove_chain_extension_handler(0, 41);
+        assert!(session
+            .call::<_, u32>("call_ce", NO_ARGS, NO_ENDOWMENT)
+            .is_err());
+
+        Ok(())
+    }
 }
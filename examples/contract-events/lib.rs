@@ -7,6 +7,12 @@ mod flipper {
         new_value: bool,
     }
 
+    /// Has the same fields as `Flipped`, so it can be told apart only by its signature topic.
+    #[ink(event)]
+    pub struct Reset {
+        new_value: bool,
+    }
+
     #[ink(storage)]
     pub struct Flipper {
         value: bool,
@@ -26,6 +32,12 @@ mod flipper {
             });
         }
 
+        #[ink(message)]
+        pub fn reset(&mut self) {
+            self.value = false;
+            self.env().emit_event(Reset { new_value: false });
+        }
+
         #[ink(message)]
         pub fn get(&self) -> bool {
             self.value
@@ -63,4 +75,32 @@ mod tests {
 
         Ok(())
     }
+
+    #[drink::test]
+    fn events_are_decoded_by_their_signature_topic(
+        mut session: Session,
+    ) -> Result<(), Box<dyn Error>> {
+        let address = session.deploy_bundle(
+            BundleProvider::local()?,
+            "new",
+            &["true"],
+            vec![],
+            NO_ENDOWMENT,
+        )?;
+
+        // `Reset` and `Flipped` events have the same fields, but they are recognized by the
+        // signature topic.
+        session.call("reset", NO_ARGS, NO_ENDOWMENT)??;
+        session
+            .record()
+            .assert_emitted(&address, "Reset", [("new_value", "false")]);
+        session.record().assert_not_emitted(&address, "Flipped", []);
+
+        session.call("flip", NO_ARGS, NO_ENDOWMENT)??;
+        session
+            .record()
+            .assert_emitted(&address, "Flipped", [("new_value", "true")]);
+
+        Ok(())
+    }
 }
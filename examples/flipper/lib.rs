@@ -32,9 +32,15 @@ mod flipper {
 
 #[cfg(test)]
 mod tests {
-    use std::error::Error;
+    use std::{error::Error, rc::Rc};
 
-    use drink::session::{Session, NO_ARGS, NO_ENDOWMENT, NO_SALT};
+    use drink::{
+        session::{
+            contract_transcode::ContractMessageTranscoder, error::SessionError, Session, NO_ARGS,
+            NO_ENDOWMENT, NO_SALT,
+        },
+        ContractBundle,
+    };
 
     #[drink::contract_bundle_provider]
     enum BundleProvider {}
@@ -54,6 +60,50 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn storage_layout_is_compatible_with_itself() -> Result<(), Box<dyn Error>> {
+        let contract = BundleProvider::local()?;
+        assert_eq!(contract.check_storage_compat(&contract), Ok(()));
+        Ok(())
+    }
+
+    /// Returns a different version of `contract`, whose `value` is declared as `u128`, so that the
+    /// storage of a deployed flipper doesn't decode with it.
+    fn incompatible_version(contract: &ContractBundle) -> Result<ContractBundle, Box<dyn Error>> {
+        let metadata = serde_json::to_string(contract.transcoder.metadata())?
+            .replace(r#""primitive":"bool""#, r#""primitive":"u128""#);
+        let mut wasm = contract.wasm.clone();
+        // An empty custom section named `drnk`, so that the code hash differs.
+        wasm.extend([0, 5, 4, b'd', b'r', b'n', b'k']);
+        Ok(ContractBundle {
+            wasm,
+            transcoder: Rc::new(ContractMessageTranscoder::new(serde_json::from_str(
+                &metadata,
+            )?)),
+        })
+    }
+
+    #[drink::test]
+    fn upgrade_to_incompatible_storage_leaves_contract_unchanged(
+        mut session: Session,
+    ) -> Result<(), Box<dyn Error>> {
+        let contract = BundleProvider::local()?;
+        let address =
+            session.deploy_bundle(contract.clone(), "new", &["true"], NO_SALT, NO_ENDOWMENT)?;
+        let entries = session.record().transcript().entries().len();
+
+        let result = session.upgrade(address.clone(), incompatible_version(&contract)?);
+        assert!(matches!(result, Err(SessionError::IncompatibleStorage(_))));
+
+        // Neither the new code was uploaded, nor was it set for the contract.
+        assert_eq!(session.record().transcript().entries().len(), entries);
+        // The contract still works with the old ABI.
+        let value: bool = session.call_with_address(address, "get", NO_ARGS, NO_ENDOWMENT)??;
+        assert!(value);
+
+        Ok(())
+    }
+
     #[drink::test]
     fn flipping(mut session: Session) -> Result<(), Box<dyn Error>> {
         let contract = BundleProvider::Flipper.bundle()?;